@@ -1,6 +1,6 @@
 #![forbid(unsafe_code)]
 
-use super::Error;
+use super::{Attrs, Error, NameEntry};
 
 use concurrent_arena::Arena;
 use derive_destructure2::destructure;
@@ -8,8 +8,15 @@ use openssh_sftp_protocol::response::ResponseInner;
 
 #[derive(Debug)]
 pub(crate) enum Response<Buffer> {
+    /// Status and handle response.
     Header(ResponseInner),
 
+    /// Name response.
+    Name(Box<[NameEntry]>),
+
+    /// Attrs response.
+    Attrs(Attrs),
+
     /// The buffer that stores the response of Read.
     ///
     /// It will be returned if you provided a buffer to
@@ -13,8 +13,8 @@ use std::{
 
 use derive_destructure2::destructure;
 use openssh_sftp_protocol::{
-    file_attrs::FileAttrs,
-    response::{NameEntry, ResponseInner, StatusCode},
+    response::{ResponseInner, StatusCode},
+    serde::Deserialize,
     ssh_format, HandleOwned,
 };
 
@@ -35,6 +35,44 @@ pub enum Data<Buffer> {
     Eof,
 }
 
+/// The response returned by [`WriteEnd::send_extended_request`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ExtendedReply {
+    /// The server replied with status success.
+    Success,
+
+    /// The server replied with status eof.
+    Eof,
+
+    /// Payload of the extended reply, excluding the packet type
+    /// and the request id.
+    Reply(Box<[u8]>),
+
+    /// The server replied with a handle.
+    Handle(HandleOwned),
+
+    /// The server replied with a list of names.
+    Name(Box<[NameEntry]>),
+
+    /// The server replied with attributes.
+    Attrs(Attrs),
+}
+
+impl ExtendedReply {
+    /// Deserialize payload of [`ExtendedReply::Reply`] into `T`.
+    ///
+    /// Any trailing bytes not consumed by `T` are ignored.
+    ///
+    /// Return [`Error::InvalidResponse`] if it is not a [`ExtendedReply::Reply`].
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T, Error> {
+        match self {
+            ExtendedReply::Reply(boxed) => Ok(ssh_format::from_bytes(boxed)?.0),
+            _ => Err(Error::InvalidResponse(&"Expected extended reply response")),
+        }
+    }
+}
+
 type AwaitableInnerRes<Buffer> = (Id<Buffer>, Response<Buffer>);
 
 #[repr(transparent)]
@@ -221,17 +259,11 @@ def_awaitable!(
     Box<[NameEntry]>,
     |response| {
         match response {
-            Response::Header(response_inner) => match response_inner {
-                ResponseInner::Name(name) => Ok(name),
-                ResponseInner::Status {
-                    status_code: StatusCode::Eof,
-                    ..
-                } => Ok(Vec::new().into_boxed_slice()),
-
-                _ => Err(Error::InvalidResponse(
-                    &"Expected Name or err Status response",
-                )),
-            },
+            Response::Name(name) => Ok(name),
+            Response::Header(ResponseInner::Status {
+                status_code: StatusCode::Eof,
+                ..
+            }) => Ok(Vec::new().into_boxed_slice()),
             _ => Err(Error::InvalidResponse(
                 &"Expected Name or err Status response",
             )),
@@ -239,23 +271,18 @@ def_awaitable!(
     }
 );
 
-def_awaitable!(
-    AwaitableAttrs,
-    AwaitableAttrsFuture,
-    FileAttrs,
-    |response| {
-        match response {
-            Response::Header(ResponseInner::Attrs(attrs)) => Ok(attrs),
-            _ => Err(Error::InvalidResponse(
-                &"Expected Attrs or err Status response",
-            )),
-        }
+def_awaitable!(AwaitableAttrs, AwaitableAttrsFuture, Attrs, |response| {
+    match response {
+        Response::Attrs(attrs) => Ok(attrs),
+        _ => Err(Error::InvalidResponse(
+            &"Expected Attrs or err Status response",
+        )),
     }
-);
+});
 
 def_awaitable!(AwaitableName, AwaitableNameFuture, Box<Path>, |response| {
     match response {
-        Response::Header(ResponseInner::Name(mut names)) => {
+        Response::Name(mut names) => {
             if names.len() != 1 {
                 Err(Error::InvalidResponse(
                     &"Got expected Name response, but it does not have exactly \
@@ -279,3 +306,82 @@ def_awaitable!(AwaitableLimits, AwaitableLimitsFuture, Limits, |response| {
         _ => Err(Error::InvalidResponse(&"Expected extended reply response")),
     }
 });
+
+def_awaitable!(
+    AwaitableFsStats,
+    AwaitableFsStatsFuture,
+    FsStats,
+    |response| {
+        match response {
+            Response::ExtendedReply(boxed) => Ok(ssh_format::from_bytes(&boxed)?.0),
+            _ => Err(Error::InvalidResponse(&"Expected extended reply response")),
+        }
+    }
+);
+
+def_awaitable!(
+    AwaitableUsersGroups,
+    AwaitableUsersGroupsFuture,
+    UsersGroups,
+    |response| {
+        match response {
+            Response::ExtendedReply(boxed) => Ok(UsersGroups::deserialize(&boxed)?),
+            _ => Err(Error::InvalidResponse(&"Expected extended reply response")),
+        }
+    }
+);
+
+def_awaitable!(
+    AwaitableFileChecksum,
+    AwaitableFileChecksumFuture,
+    FileChecksum,
+    |response| {
+        match response {
+            Response::ExtendedReply(boxed) => Ok(FileChecksum::deserialize(&boxed)?),
+            _ => Err(Error::InvalidResponse(&"Expected extended reply response")),
+        }
+    }
+);
+
+def_awaitable!(
+    AwaitableMd5Hash,
+    AwaitableMd5HashFuture,
+    Box<[u8]>,
+    |response| {
+        match response {
+            Response::ExtendedReply(boxed) => Ok(checksum::deserialize_md5_hash(&boxed)?),
+            _ => Err(Error::InvalidResponse(&"Expected extended reply response")),
+        }
+    }
+);
+
+def_awaitable!(
+    AwaitableExtendedReply,
+    AwaitableExtendedReplyFuture,
+    ExtendedReply,
+    |response| {
+        match response {
+            Response::ExtendedReply(boxed) => Ok(ExtendedReply::Reply(boxed)),
+            Response::Header(ResponseInner::Status {
+                status_code: StatusCode::Success,
+                ..
+            }) => Ok(ExtendedReply::Success),
+            Response::Header(ResponseInner::Status {
+                status_code: StatusCode::Eof,
+                ..
+            }) => Ok(ExtendedReply::Eof),
+            Response::Header(ResponseInner::Handle(handle)) => {
+                if handle.into_inner().len() > 256 {
+                    Err(Error::HandleTooLong)
+                } else {
+                    Ok(ExtendedReply::Handle(handle))
+                }
+            }
+            Response::Name(names) => Ok(ExtendedReply::Name(names)),
+            Response::Attrs(attrs) => Ok(ExtendedReply::Attrs(attrs)),
+            _ => Err(Error::InvalidResponse(
+                &"Expected extended reply, status, handle, name or attrs response",
+            )),
+        }
+    }
+);
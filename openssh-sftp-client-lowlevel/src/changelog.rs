@@ -1,7 +1,41 @@
 #[allow(unused_imports)]
 use crate::*;
 
-#[doc(hidden)]
+/// ## Added
+///  - [`WriteEnd::send_statvfs_request`] and [`WriteEnd::send_fstatvfs_request`]
+///    for the `statvfs@openssh.com` and `fstatvfs@openssh.com` extensions
+///  - [`FsStats`], [`AwaitableFsStats`] and [`AwaitableFsStatsFuture`]
+///  - [`WriteEnd::send_lsetstat_request`] for the `lsetstat@openssh.com` extension
+///  - [`WriteEnd::send_users_groups_by_id_request`] for the
+///    `users-groups-by-id@openssh.com` extension
+///  - [`UsersGroups`], [`AwaitableUsersGroups`] and [`AwaitableUsersGroupsFuture`]
+///  - [`WriteEnd::send_home_directory_request`] for the `home-directory` extension
+///  - [`connect_with_version`] to negotiate sftp v4 to v6, bounded by
+///    [`SFTP_MIN_VERSION`] and [`SFTP_MAX_VERSION`]
+///  - [`SharedData::sftp_version`] to get the negotiated sftp version
+///  - [`Attrs`], [`FileAttrsExt`] and [`FileTime`] for attributes only
+///    available in sftp v4 and later
+///  - [`WriteEnd::send_check_file_handle_request`] and
+///    [`WriteEnd::send_check_file_name_request`] for the `check-file` extension
+///  - [`WriteEnd::send_md5_hash_handle_request`] and [`WriteEnd::send_md5_hash_request`]
+///    for the `md5-hash` extension
+///  - [`FileChecksum`], [`hash_len`], [`AwaitableFileChecksum`],
+///    [`AwaitableFileChecksumFuture`], [`AwaitableMd5Hash`] and [`AwaitableMd5HashFuture`]
+///  - [`SharedData::server_extension_data`] to get data of extensions
+///    advertised by the server
+///  - [`SharedData::server_version`] and [`SharedData::server_extensions`] to get
+///    the version and all extensions advertised by the server
+///  - [`WriteEnd::send_copy_file_request`] for the `copy-file` extension
+///  - [`WriteEnd::send_extended_request`] to send arbitrary extended requests,
+///    with the response returned as [`ExtendedReply`] by [`AwaitableExtendedReply`]
+///    and [`AwaitableExtendedReplyFuture`]
+///
+/// ## Changed
+///  - [`AwaitableAttrs`] now returns [`Attrs`] instead of [`FileAttrs`]
+///  - [`NameEntry`] is now defined in this crate and contains [`Attrs`]
+///  - [`ReadEnd::receive_server_hello`] accepts any server version newer than 3
+///    and uses the lower one of it and the proposed version
+///  - Bump dependency `openssh-sftp-error` to v0.5.0.
 pub mod unreleased {}
 
 /// # Changed
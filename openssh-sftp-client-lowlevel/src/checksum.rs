@@ -0,0 +1,108 @@
+#![forbid(unsafe_code)]
+
+use openssh_sftp_protocol::ssh_format;
+
+/// Payload of extended reply response when
+/// [`crate::WriteEnd::send_check_file_handle_request`] or
+/// [`crate::WriteEnd::send_check_file_name_request`] is sent.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FileChecksum {
+    /// The hash algorithm the server picked from the requested list.
+    pub algorithm: Box<str>,
+
+    /// Hashes of each block concatenated together, or the hash of
+    /// the whole range if the block size in the request is `0`.
+    pub hashes: Box<[u8]>,
+}
+
+impl FileChecksum {
+    pub(crate) fn deserialize(bytes: &[u8]) -> Result<Self, ssh_format::Error> {
+        // The reply starts with the string "check-file".
+        let ((_name, algorithm), hashes): ((Vec<u8>, Vec<u8>), _) = ssh_format::from_bytes(bytes)?;
+
+        Ok(Self {
+            algorithm: String::from_utf8_lossy(&algorithm).into(),
+            hashes: hashes.into(),
+        })
+    }
+
+    /// Return length of the hash produced by [`FileChecksum::algorithm`],
+    /// or `None` if the algorithm is unknown.
+    pub fn hash_len(&self) -> Option<usize> {
+        hash_len(&self.algorithm)
+    }
+
+    /// Return the hash of each block.
+    ///
+    /// Return `None` if the algorithm is unknown or [`FileChecksum::hashes`]
+    /// is not a multiple of the length of the hash.
+    pub fn blocks(&self) -> Option<impl Iterator<Item = &[u8]> + '_> {
+        let blocks = self.hashes.chunks_exact(self.hash_len()?);
+
+        if blocks.remainder().is_empty() {
+            Some(blocks)
+        } else {
+            None
+        }
+    }
+}
+
+/// Return length of the hash produced by hash algorithm `algorithm`
+/// used in the `check-file` extension, or `None` if it is unknown.
+pub fn hash_len(algorithm: &str) -> Option<usize> {
+    match algorithm {
+        "crc32" => Some(4),
+        "md5" => Some(16),
+        "sha1" => Some(20),
+        "sha224" => Some(28),
+        "sha256" => Some(32),
+        "sha384" => Some(48),
+        "sha512" => Some(64),
+        _ => None,
+    }
+}
+
+/// Parse payload of extended reply response when
+/// [`crate::WriteEnd::send_md5_hash_handle_request`] or
+/// [`crate::WriteEnd::send_md5_hash_request`] is sent.
+pub(crate) fn deserialize_md5_hash(bytes: &[u8]) -> Result<Box<[u8]>, ssh_format::Error> {
+    // The reply starts with the string "md5-hash".
+    let ((_name, hash), _): ((Vec<u8>, Vec<u8>), _) = ssh_format::from_bytes(bytes)?;
+
+    Ok(hash.into_boxed_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ssh_string(s: &[u8]) -> Vec<u8> {
+        let mut bytes = (s.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(s);
+        bytes
+    }
+
+    #[test]
+    fn test_deserialize_file_checksum() {
+        let mut bytes = ssh_string(b"check-file");
+        bytes.extend(ssh_string(b"md5"));
+        bytes.extend([1; 16]);
+        bytes.extend([2; 16]);
+
+        let checksum = FileChecksum::deserialize(&bytes).unwrap();
+
+        assert_eq!(&*checksum.algorithm, "md5");
+        assert_eq!(checksum.hashes.len(), 32);
+
+        let blocks: Vec<_> = checksum.blocks().unwrap().collect();
+        assert_eq!(blocks, [&[1; 16][..], &[2; 16][..]]);
+    }
+
+    #[test]
+    fn test_deserialize_md5_hash() {
+        let mut bytes = ssh_string(b"md5-hash");
+        bytes.extend(ssh_string(&[3; 16]));
+
+        assert_eq!(&*deserialize_md5_hash(&bytes).unwrap(), &[3; 16]);
+    }
+}
@@ -2,18 +2,41 @@
 
 use super::{awaitable_responses::AwaitableResponses, *};
 
-use std::{fmt, sync::Arc};
-
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use once_cell::sync::OnceCell;
 use openssh_sftp_protocol::constants::SSH2_FILEXFER_VERSION;
 
 // TODO:
 //  - Support for zero copy syscalls
 
+/// Extensions (name, data) advertised in the hello message from the server.
+pub(crate) type ServerExtensions = Box<[(Box<str>, Box<[u8]>)]>;
+
+/// The hello message from the server.
+#[derive(Debug)]
+struct ServerHello {
+    version: u32,
+    extensions: ServerExtensions,
+}
+
 #[derive(Debug)]
 struct SharedDataInner<Buffer, Q, Auxiliary> {
     queue: Q,
     responses: AwaitableResponses<Buffer>,
 
+    /// The sftp version proposed by the client, replaced by the negotiated
+    /// version once the hello message from the server is received.
+    version: AtomicU32,
+
+    server_hello: OnceCell<ServerHello>,
+
     auxiliary: Auxiliary,
 }
 
@@ -38,11 +61,14 @@ impl<Buffer, Q, Auxiliary> Clone for SharedData<Buffer, Q, Auxiliary> {
 }
 
 impl<Buffer: Send + Sync, Q, Auxiliary> SharedData<Buffer, Q, Auxiliary> {
-    fn new(queue: Q, auxiliary: Auxiliary) -> Self {
+    fn new(queue: Q, auxiliary: Auxiliary, version: u32) -> Self {
         SharedData(Arc::new(SharedDataInner {
             responses: AwaitableResponses::new(),
             queue,
 
+            version: AtomicU32::new(version),
+            server_hello: OnceCell::new(),
+
             auxiliary,
         }))
     }
@@ -61,6 +87,60 @@ impl<Buffer, Q, Auxiliary> SharedData<Buffer, Q, Auxiliary> {
     pub fn get_auxiliary(&self) -> &Auxiliary {
         &self.0.auxiliary
     }
+
+    /// Return the negotiated sftp version.
+    ///
+    /// Before [`ReadEnd::receive_server_hello`] returns, it is the version
+    /// proposed by the client.
+    pub fn sftp_version(&self) -> u32 {
+        self.0.version.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_sftp_version(&self, version: u32) {
+        self.0.version.store(version, Ordering::Relaxed);
+    }
+
+    /// Return the sftp version sent by the server in its hello message,
+    /// which might be newer than [`SharedData::sftp_version`].
+    ///
+    /// Return `None` if [`ReadEnd::receive_server_hello`] has not returned yet.
+    pub fn server_version(&self) -> Option<u32> {
+        self.0.server_hello.get().map(|hello| hello.version)
+    }
+
+    /// Return (name, data) of all extensions advertised by the server,
+    /// in the order they appear in its hello message.
+    ///
+    /// It is empty if [`ReadEnd::receive_server_hello`] has not returned yet.
+    pub fn server_extensions(&self) -> impl Iterator<Item = (&str, &[u8])> + '_ {
+        self.0
+            .server_hello
+            .get()
+            .into_iter()
+            .flat_map(|hello| hello.extensions.iter())
+            .map(|(name, data)| (&**name, &**data))
+    }
+
+    /// Return data of extension `name` advertised by the server.
+    ///
+    /// Return `None` if the server does not advertise it or
+    /// [`ReadEnd::receive_server_hello`] has not returned yet.
+    pub fn server_extension_data(&self, name: &str) -> Option<&[u8]> {
+        self.server_extensions()
+            .find(|(ext_name, _data)| *ext_name == name)
+            .map(|(_name, data)| data)
+    }
+
+    pub(crate) fn set_server_hello(&self, version: u32, extensions: ServerExtensions) {
+        // The hello message is only received once.
+        self.0
+            .server_hello
+            .set(ServerHello {
+                version,
+                extensions,
+            })
+            .ok();
+    }
 }
 
 impl<Buffer: Send + Sync, Q, Auxiliary> SharedData<Buffer, Q, Auxiliary> {
@@ -102,11 +182,34 @@ where
     Buffer: ToBuffer + Send + Sync + 'static,
     Q: Queue,
 {
-    let shared_data = SharedData::new(queue, auxiliary);
+    connect_with_version(queue, auxiliary, SSH2_FILEXFER_VERSION)
+}
+
+/// Same as [`connect`], except that it proposes sftp `version` to the server.
+///
+/// The lower one of `version` and the version returned by the server is used,
+/// which can be obtained by [`SharedData::sftp_version`] after
+/// [`ReadEnd::receive_server_hello`] returns.
+///
+/// `version` must be within [`SFTP_MIN_VERSION`] and [`SFTP_MAX_VERSION`].
+pub fn connect_with_version<Buffer, Q, Auxiliary>(
+    queue: Q,
+    auxiliary: Auxiliary,
+    version: u32,
+) -> Result<WriteEnd<Buffer, Q, Auxiliary>, Error>
+where
+    Buffer: ToBuffer + Send + Sync + 'static,
+    Q: Queue,
+{
+    if !(SFTP_MIN_VERSION..=SFTP_MAX_VERSION).contains(&version) {
+        return Err(Error::UnsupportedSftpProtocol { version });
+    }
+
+    let shared_data = SharedData::new(queue, auxiliary, version);
 
     // Send hello message
     let mut write_end = WriteEnd::new(shared_data);
-    write_end.send_hello(SSH2_FILEXFER_VERSION)?;
+    write_end.send_hello(version)?;
 
     Ok(write_end)
 }
@@ -0,0 +1,129 @@
+#![forbid(unsafe_code)]
+
+use super::FileAttrs;
+
+use std::{
+    convert::TryInto,
+    ops::{Deref, DerefMut},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// Timestamp with optional sub-second precision used by sftp v4 and later.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FileTime {
+    /// Seconds since 1970-01-01 00:00:00 UTC, can be negative.
+    pub seconds: i64,
+    /// Nanoseconds, `0` if the server did not send sub-second times.
+    pub nanoseconds: u32,
+}
+
+impl FileTime {
+    /// Return `None` if [`SystemTime`] cannot hold the timestamp.
+    pub fn as_system_time(&self) -> Option<SystemTime> {
+        let nanos = Duration::from_nanos(self.nanoseconds.into());
+
+        if self.seconds >= 0 {
+            SystemTime::UNIX_EPOCH
+                .checked_add(Duration::from_secs(self.seconds.try_into().ok()?))?
+                .checked_add(nanos)
+        } else {
+            SystemTime::UNIX_EPOCH
+                .checked_sub(Duration::from_secs(self.seconds.unsigned_abs()))?
+                .checked_add(nanos)
+        }
+    }
+}
+
+/// Attributes that are only available in sftp v4 and later.
+///
+/// All fields are `None` if the server did not send them.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct FileAttrsExt {
+    /// Raw `SSH_FILEXFER_TYPE_*` of the file.
+    pub file_type: u8,
+
+    /// Owner of the file, usually in the form of `user@domain`.
+    pub owner: Option<Box<str>>,
+    /// Group of the file, usually in the form of `group@domain`.
+    pub group: Option<Box<str>>,
+
+    /// Last access time.
+    pub accessed: Option<FileTime>,
+    /// Creation time.
+    pub created: Option<FileTime>,
+    /// Last modification time.
+    pub modified: Option<FileTime>,
+    /// Last time the attributes were changed, only available in sftp v6.
+    pub changed: Option<FileTime>,
+
+    /// Raw access control list, consists of the ace count followed by the aces.
+    pub acl: Option<Box<[u8]>>,
+
+    /// `SSH_FILEXFER_ATTR_FLAGS_*` bits, only available in sftp v5 and later.
+    pub attrib_bits: Option<u32>,
+    /// Bits in `attrib_bits` that the server understands, only available in sftp v6.
+    pub attrib_bits_valid: Option<u32>,
+
+    /// Number of bytes allocated on disk, only available in sftp v6.
+    pub allocation_size: Option<u64>,
+    /// `SSH_FILEXFER_ATTR_KNOWN_*` hint, only available in sftp v6.
+    pub text_hint: Option<u8>,
+    /// Mime type, only available in sftp v6.
+    pub mime_type: Option<Box<str>>,
+    /// Number of hard links, only available in sftp v6.
+    pub link_count: Option<u32>,
+    /// Name of the file before the server translates it, only available in sftp v6.
+    pub untranslated_name: Option<Box<[u8]>>,
+}
+
+/// File attributes returned by the server.
+///
+/// It dereferences to [`FileAttrs`], which contains the attributes available
+/// in every version of sftp, while [`Attrs::ext`] contains the attributes
+/// only available in sftp v4 and later.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Attrs {
+    /// Attributes available in every version of sftp.
+    pub attrs: FileAttrs,
+    /// `None` if the negotiated sftp version is 3.
+    pub ext: Option<Box<FileAttrsExt>>,
+}
+
+impl Attrs {
+    /// Return the [`FileAttrs`], discarding [`Attrs::ext`].
+    pub fn into_inner(self) -> FileAttrs {
+        self.attrs
+    }
+}
+
+impl From<FileAttrs> for Attrs {
+    fn from(attrs: FileAttrs) -> Self {
+        Self { attrs, ext: None }
+    }
+}
+
+impl Deref for Attrs {
+    type Target = FileAttrs;
+
+    fn deref(&self) -> &Self::Target {
+        &self.attrs
+    }
+}
+
+impl DerefMut for Attrs {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.attrs
+    }
+}
+
+/// Entry in a name response.
+#[derive(Debug, Clone)]
+pub struct NameEntry {
+    /// The filename.
+    pub filename: Box<Path>,
+
+    /// Attributes of the file.
+    pub attrs: Attrs,
+}
@@ -0,0 +1,42 @@
+#![forbid(unsafe_code)]
+
+use openssh_sftp_protocol::serde::Deserialize;
+
+/// Payload of extended reply response when [`crate::WriteEnd::send_statvfs_request`]
+/// or [`crate::WriteEnd::send_fstatvfs_request`] is sent.
+///
+/// The fields are in the same order as `struct statvfs` in POSIX.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize)]
+#[serde(crate = "openssh_sftp_protocol::serde")]
+pub struct FsStats {
+    /// Filesystem block size.
+    pub block_size: u64,
+    /// Fundamental filesystem block size.
+    pub fragment_size: u64,
+    /// Size of filesystem in `fragment_size` units.
+    pub blocks: u64,
+    /// Number of free blocks.
+    pub blocks_free: u64,
+    /// Number of free blocks for unprivileged users.
+    pub blocks_avail: u64,
+    /// Number of inodes.
+    pub files: u64,
+    /// Number of free inodes.
+    pub files_free: u64,
+    /// Number of free inodes for unprivileged users.
+    pub files_avail: u64,
+    /// Filesystem ID.
+    pub fsid: u64,
+    /// Mount flags, see [`FsStats::RDONLY`] and [`FsStats::NOSUID`].
+    pub flags: u64,
+    /// Maximum filename length.
+    pub name_max: u64,
+}
+
+impl FsStats {
+    /// Read-only filesystem.
+    pub const RDONLY: u64 = 0x1;
+
+    /// Filesystem does not support setuid/setgid semantics.
+    pub const NOSUID: u64 = 0x2;
+}
@@ -8,23 +8,43 @@
 //! To create [`WriteEnd`] and [`ReadEnd`], simply pass the `stdin` and `stdout` of
 //! the `sftp-server` launched at remote to [`connect`].
 //!
-//! This crate supports all operations supported by sftp v3, in additional to
+//! This crate supports all operations supported by sftp v3 and can negotiate
+//! sftp v4 to v6 using [`connect_with_version`], in additional to
 //! the following extensions:
 //!  - [`WriteEnd::send_limits_request`]
 //!  - [`WriteEnd::send_expand_path_request`]
 //!  - [`WriteEnd::send_fsync_request`]
 //!  - [`WriteEnd::send_hardlink_request`]
 //!  - [`WriteEnd::send_posix_rename_request`]
+//!  - [`WriteEnd::send_copy_data_request`]
+//!  - [`WriteEnd::send_statvfs_request`]
+//!  - [`WriteEnd::send_fstatvfs_request`]
+//!  - [`WriteEnd::send_lsetstat_request`]
+//!  - [`WriteEnd::send_users_groups_by_id_request`]
+//!  - [`WriteEnd::send_home_directory_request`]
+//!  - [`WriteEnd::send_check_file_handle_request`]
+//!  - [`WriteEnd::send_check_file_name_request`]
+//!  - [`WriteEnd::send_md5_hash_handle_request`]
+//!  - [`WriteEnd::send_md5_hash_request`]
+//!  - [`WriteEnd::send_copy_file_request`]
+//!
+//! Other extensions can be used via [`WriteEnd::send_extended_request`].
 
 pub use openssh_sftp_error::{Error, SftpErrMsg, SftpErrorKind, UnixTimeStampError};
 pub use openssh_sftp_protocol::{
     file_attrs::{FileAttrs, FileType, Permissions, UnixTimeStamp},
     open_options::{CreateFlags, OpenOptions},
     request::OpenFileRequest,
-    response::{Extensions, Limits, NameEntry},
+    response::{Extensions, Limits},
     Handle, HandleOwned,
 };
 
+/// Minimum sftp version supported.
+pub const SFTP_MIN_VERSION: u32 = 3;
+
+/// Maximum sftp version supported.
+pub const SFTP_MAX_VERSION: u32 = 6;
+
 /// Default size of buffer for up/download in openssh-portable
 pub const OPENSSH_PORTABLE_DEFAULT_COPY_BUFLEN: usize = 32768;
 
@@ -52,17 +72,34 @@ pub use awaitable_responses::Id;
 
 mod awaitables;
 pub use awaitables::{
-    AwaitableAttrs, AwaitableAttrsFuture, AwaitableData, AwaitableDataFuture, AwaitableHandle,
-    AwaitableHandleFuture, AwaitableLimits, AwaitableLimitsFuture, AwaitableName,
-    AwaitableNameEntries, AwaitableNameEntriesFuture, AwaitableNameFuture, AwaitableStatus,
-    AwaitableStatusFuture, Data,
+    AwaitableAttrs, AwaitableAttrsFuture, AwaitableData, AwaitableDataFuture,
+    AwaitableExtendedReply, AwaitableExtendedReplyFuture, AwaitableFileChecksum,
+    AwaitableFileChecksumFuture, AwaitableFsStats, AwaitableFsStatsFuture, AwaitableHandle,
+    AwaitableHandleFuture, AwaitableLimits, AwaitableLimitsFuture, AwaitableMd5Hash,
+    AwaitableMd5HashFuture, AwaitableName, AwaitableNameEntries, AwaitableNameEntriesFuture,
+    AwaitableNameFuture, AwaitableStatus, AwaitableStatusFuture, AwaitableUsersGroups,
+    AwaitableUsersGroupsFuture, Data, ExtendedReply,
 };
 
+mod fs_stats;
+pub use fs_stats::FsStats;
+
+mod file_attrs_ext;
+pub use file_attrs_ext::{Attrs, FileAttrsExt, FileTime, NameEntry};
+
+mod versioned;
+
+mod users_groups;
+pub use users_groups::UsersGroups;
+
+mod checksum;
+pub use checksum::{hash_len, FileChecksum};
+
 mod buffer;
 pub use buffer::{Buffer, ToBuffer};
 
 mod connection;
-pub use connection::{connect, SharedData};
+pub use connection::{connect, connect_with_version, SharedData};
 
 mod queue;
 pub use queue::Queue;
@@ -1,15 +1,17 @@
 #![forbid(unsafe_code)]
 
 use super::{
-    awaitable_responses::ArenaArc, awaitable_responses::Response, connection::SharedData,
-    reader_buffered::ReaderBuffered, Error, Extensions, ToBuffer,
+    awaitable_responses::ArenaArc,
+    awaitable_responses::Response,
+    connection::{ServerExtensions, SharedData},
+    reader_buffered::ReaderBuffered,
+    versioned, Error, Extensions, ToBuffer, SFTP_MIN_VERSION,
 };
 
 use std::{io, num::NonZeroUsize, pin::Pin};
 
 use openssh_sftp_error::RecursiveError;
 use openssh_sftp_protocol::{
-    constants::SSH2_FILEXFER_VERSION,
     response::{self, ServerVersion},
     serde::de::DeserializeOwned,
     ssh_format::{self, from_bytes},
@@ -18,6 +20,24 @@ use pin_project::pin_project;
 use tokio::io::{copy_buf, sink, AsyncBufReadExt, AsyncRead, AsyncReadExt};
 use tokio_io_utility::{read_exact_to_bytes, read_exact_to_vec};
 
+/// Parse the (name, data) pairs of extensions in the hello message,
+/// which consists of the packet type, the version and then the pairs.
+fn parse_server_extensions(bytes: &[u8]) -> Result<ServerExtensions, Error> {
+    let (_header, mut bytes): ((u8, u32), _) = from_bytes(bytes)?;
+    let mut extensions = Vec::new();
+
+    while !bytes.is_empty() {
+        let ((name, data), rest): ((Vec<u8>, Vec<u8>), _) = from_bytes(bytes)?;
+        extensions.push((
+            String::from_utf8_lossy(&name).into(),
+            data.into_boxed_slice(),
+        ));
+        bytes = rest;
+    }
+
+    Ok(extensions.into_boxed_slice())
+}
+
 /// The ReadEnd for the lowlevel API.
 #[derive(Debug)]
 #[pin_project]
@@ -57,19 +77,23 @@ where
             return Err(Error::SftpServerHelloMsgTooLong { len });
         }
 
-        let drain = self
-            .project()
-            .reader
-            .read_exact_into_buffer(len as usize)
-            .await?;
+        let this = self.project();
+        let drain = this.reader.read_exact_into_buffer(len as usize).await?;
         let server_version =
             ServerVersion::deserialize(&mut ssh_format::Deserializer::from_bytes(&drain))?;
+        let server_extensions = parse_server_extensions(&drain)?;
 
-        if server_version.version != SSH2_FILEXFER_VERSION {
+        // Both sides use the lower one of the versions they proposed.
+        let version = server_version.version.min(this.shared_data.sftp_version());
+
+        if version < SFTP_MIN_VERSION {
             Err(Error::UnsupportedSftpProtocol {
                 version: server_version.version,
             })
         } else {
+            this.shared_data.set_sftp_version(version);
+            this.shared_data
+                .set_server_hello(server_version.version, server_extensions);
             Ok(server_version.extensions)
         }
     }
@@ -115,14 +139,29 @@ where
         buffer: Option<Buffer>,
     ) -> Result<Response<Buffer>, Error> {
         // Since the data is sent as a string, we need to consume the 4-byte length first.
-        self.as_mut()
-            .project()
-            .reader
-            .read_exact_into_buffer(4)
-            .await?;
+        let data_len: u32 = self.as_mut().read_and_deserialize(4).await?;
+
+        // sftp v6 might send an end-of-file boolean after the data.
+        let trailing = (len - 4).saturating_sub(data_len);
+        let len = (len - 4 - trailing) as usize;
+
+        let response = self.as_mut().read_in_data(len, buffer).await?;
 
-        let len = (len - 4) as usize;
+        if trailing != 0 {
+            self.project()
+                .reader
+                .read_exact_into_buffer(trailing as usize)
+                .await?;
+        }
+
+        Ok(response)
+    }
 
+    async fn read_in_data(
+        self: Pin<&mut Self>,
+        len: usize,
+        buffer: Option<Buffer>,
+    ) -> Result<Response<Buffer>, Error> {
         if let Some(mut buffer) = buffer {
             match buffer.get_buffer() {
                 super::Buffer::Vector(vec) => {
@@ -149,9 +188,11 @@ where
 
     /// * `len` - includes packet_type and request_id.
     async fn read_in_packet(self: Pin<&mut Self>, len: u32) -> Result<Response<Buffer>, Error> {
-        let response: response::Response = self.read_and_deserialize(len as usize).await?;
+        let this = self.project();
+        let version = this.shared_data.sftp_version();
+        let drain = this.reader.read_exact_into_buffer(len as usize).await?;
 
-        Ok(Response::Header(response.response_inner))
+        versioned::parse_response(&drain, version)
     }
 
     /// * `len` - excludes packet_type and request_id.
@@ -0,0 +1,40 @@
+#![forbid(unsafe_code)]
+
+use openssh_sftp_protocol::ssh_format;
+
+/// Payload of extended reply response when
+/// [`crate::WriteEnd::send_users_groups_by_id_request`] is sent.
+///
+/// The names are in the same order as the ids in the request.
+/// An empty name means that the server failed to resolve that id.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct UsersGroups {
+    /// Names of the requested users.
+    pub users: Box<[Box<str>]>,
+    /// Names of the requested groups.
+    pub groups: Box<[Box<str>]>,
+}
+
+impl UsersGroups {
+    pub(crate) fn deserialize(bytes: &[u8]) -> Result<Self, ssh_format::Error> {
+        let ((users, groups), _): ((Vec<u8>, Vec<u8>), _) = ssh_format::from_bytes(bytes)?;
+
+        Ok(Self {
+            users: parse_names(&users)?,
+            groups: parse_names(&groups)?,
+        })
+    }
+}
+
+/// Parse a sequence of concatenated ssh strings.
+fn parse_names(mut bytes: &[u8]) -> Result<Box<[Box<str>]>, ssh_format::Error> {
+    let mut names = Vec::new();
+
+    while !bytes.is_empty() {
+        let (name, rest): (Vec<u8>, _) = ssh_format::from_bytes(bytes)?;
+        names.push(String::from_utf8_lossy(&name).into());
+        bytes = rest;
+    }
+
+    Ok(names.into_boxed_slice())
+}
@@ -0,0 +1,708 @@
+#![forbid(unsafe_code)]
+
+//! Wire format of sftp v4 to v6, for the requests and responses
+//! that differ from sftp v3.
+
+use super::{
+    awaitable_responses::Response, Attrs, Error, FileAttrs, FileAttrsExt, FileTime, FileType,
+    NameEntry, SftpErrMsg, SftpErrorKind,
+};
+
+use std::{convert::TryInto, path::Path};
+
+use openssh_sftp_protocol::{
+    constants,
+    request::{OpenFileRequest, Request, RequestInner},
+    response::{self, ResponseInner, StatusCode},
+    serde::{
+        ser::{Error as _, SerializeTuple, Serializer},
+        Deserialize, Serialize,
+    },
+    ssh_format,
+};
+
+const SSH_FXP_LINK: u8 = 21;
+
+const SSH_FILEXFER_ATTR_SIZE: u32 = 0x0000_0001;
+const SSH_FILEXFER_ATTR_UIDGID: u32 = 0x0000_0002;
+const SSH_FILEXFER_ATTR_PERMISSIONS: u32 = 0x0000_0004;
+const SSH_FILEXFER_ATTR_ACCESSTIME: u32 = 0x0000_0008;
+const SSH_FILEXFER_ATTR_CREATETIME: u32 = 0x0000_0010;
+const SSH_FILEXFER_ATTR_MODIFYTIME: u32 = 0x0000_0020;
+const SSH_FILEXFER_ATTR_ACL: u32 = 0x0000_0040;
+const SSH_FILEXFER_ATTR_OWNERGROUP: u32 = 0x0000_0080;
+const SSH_FILEXFER_ATTR_SUBSECOND_TIMES: u32 = 0x0000_0100;
+const SSH_FILEXFER_ATTR_BITS: u32 = 0x0000_0200;
+const SSH_FILEXFER_ATTR_ALLOCATION_SIZE: u32 = 0x0000_0400;
+const SSH_FILEXFER_ATTR_TEXT_HINT: u32 = 0x0000_0800;
+const SSH_FILEXFER_ATTR_MIME_TYPE: u32 = 0x0000_1000;
+const SSH_FILEXFER_ATTR_LINK_COUNT: u32 = 0x0000_2000;
+const SSH_FILEXFER_ATTR_UNTRANSLATED_NAME: u32 = 0x0000_4000;
+const SSH_FILEXFER_ATTR_CTIME: u32 = 0x0000_8000;
+const SSH_FILEXFER_ATTR_EXTENDED: u32 = 0x8000_0000;
+
+const SSH_FILEXFER_TYPE_REGULAR: u8 = 1;
+const SSH_FILEXFER_TYPE_DIRECTORY: u8 = 2;
+const SSH_FILEXFER_TYPE_SYMLINK: u8 = 3;
+const SSH_FILEXFER_TYPE_UNKNOWN: u8 = 5;
+const SSH_FILEXFER_TYPE_SOCKET: u8 = 6;
+const SSH_FILEXFER_TYPE_CHAR_DEVICE: u8 = 7;
+const SSH_FILEXFER_TYPE_BLOCK_DEVICE: u8 = 8;
+const SSH_FILEXFER_TYPE_FIFO: u8 = 9;
+
+const ACE4_READ_DATA: u32 = 0x0000_0001;
+const ACE4_WRITE_DATA: u32 = 0x0000_0002;
+const ACE4_APPEND_DATA: u32 = 0x0000_0004;
+const ACE4_READ_ATTRIBUTES: u32 = 0x0000_0080;
+const ACE4_WRITE_ATTRIBUTES: u32 = 0x0000_0100;
+
+const SSH_FXF_CREATE_NEW: u32 = 0x0000_0000;
+const SSH_FXF_CREATE_TRUNCATE: u32 = 0x0000_0001;
+const SSH_FXF_OPEN_EXISTING: u32 = 0x0000_0002;
+const SSH_FXF_OPEN_OR_CREATE: u32 = 0x0000_0003;
+const SSH_FXF_TRUNCATE_EXISTING: u32 = 0x0000_0004;
+const SSH_FXF_APPEND_DATA: u32 = 0x0000_0008;
+
+/// bit mask for the file type bit field
+const S_IFMT: u32 = 0o170000;
+
+/// Attributes to request in stat, lstat and fstat.
+fn desired_attrs(version: u32) -> u32 {
+    let mut flags = SSH_FILEXFER_ATTR_SIZE
+        | SSH_FILEXFER_ATTR_PERMISSIONS
+        | SSH_FILEXFER_ATTR_ACCESSTIME
+        | SSH_FILEXFER_ATTR_CREATETIME
+        | SSH_FILEXFER_ATTR_MODIFYTIME
+        | SSH_FILEXFER_ATTR_ACL
+        | SSH_FILEXFER_ATTR_OWNERGROUP
+        | SSH_FILEXFER_ATTR_SUBSECOND_TIMES;
+
+    if version >= 5 {
+        flags |= SSH_FILEXFER_ATTR_BITS;
+    }
+    if version >= 6 {
+        flags |= SSH_FILEXFER_ATTR_ALLOCATION_SIZE
+            | SSH_FILEXFER_ATTR_TEXT_HINT
+            | SSH_FILEXFER_ATTR_MIME_TYPE
+            | SSH_FILEXFER_ATTR_LINK_COUNT
+            | SSH_FILEXFER_ATTR_UNTRANSLATED_NAME
+            | SSH_FILEXFER_ATTR_CTIME;
+    }
+
+    flags
+}
+
+/// [`FileAttrs`] in the format of sftp v4 and later.
+///
+/// uid and gid are sent as owner and group strings.
+struct AttrsV4<'a> {
+    attrs: &'a FileAttrs,
+    file_type: u8,
+}
+
+impl Serialize for AttrsV4<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let attrs = self.attrs;
+
+        // sftp v4+ takes owner and group as `user@domain` names,
+        // which cannot be derived from uid and gid.
+        if attrs.get_id().is_some() {
+            return Err(S::Error::custom(
+                "uid and gid cannot be sent to sftp v4+ servers, which take owner and group names",
+            ));
+        }
+
+        let mut flags = 0;
+        if attrs.get_size().is_some() {
+            flags |= SSH_FILEXFER_ATTR_SIZE;
+        }
+        if attrs.get_permissions().is_some() {
+            flags |= SSH_FILEXFER_ATTR_PERMISSIONS;
+        }
+        if attrs.get_time().is_some() {
+            flags |= SSH_FILEXFER_ATTR_ACCESSTIME | SSH_FILEXFER_ATTR_MODIFYTIME;
+        }
+
+        // dummy size since ssh_format doesn't care
+        let mut tuple_serializer = serializer.serialize_tuple(1)?;
+
+        tuple_serializer.serialize_element(&flags)?;
+        tuple_serializer.serialize_element(&self.file_type)?;
+
+        if let Some(size) = attrs.get_size() {
+            tuple_serializer.serialize_element(&size)?;
+        }
+        if let Some(perm) = attrs.get_permissions() {
+            tuple_serializer.serialize_element(&perm.bits())?;
+        }
+        if let Some((atime, mtime)) = attrs.get_time() {
+            tuple_serializer.serialize_element(&i64::from(atime.into_raw()))?;
+            tuple_serializer.serialize_element(&i64::from(mtime.into_raw()))?;
+        }
+
+        tuple_serializer.end()
+    }
+}
+
+fn attrs_v4(attrs: &FileAttrs) -> AttrsV4<'_> {
+    AttrsV4 {
+        attrs,
+        file_type: SSH_FILEXFER_TYPE_UNKNOWN,
+    }
+}
+
+/// Fields of [`OpenFileRequest`] are private, so serialize it and read them back.
+fn destructure_open_request(
+    params: &OpenFileRequest<'_>,
+) -> Result<(Box<[u8]>, u32, FileAttrs), Error> {
+    let serialized = ssh_format::to_bytes(params)?;
+
+    // Skip the 4-byte length of the packet.
+    Ok(ssh_format::from_bytes(&serialized[4..])?.0)
+}
+
+/// [`Request`] in the format of the negotiated sftp version.
+pub(crate) enum VersionedRequest<'a> {
+    /// Request that is the same as in sftp v3.
+    V3(Request<'a>),
+
+    /// Request that differs from sftp v3.
+    V4 {
+        version: u32,
+        request_id: u32,
+        inner: RequestInner<'a>,
+    },
+}
+
+impl<'a> VersionedRequest<'a> {
+    pub(crate) fn new(version: u32, request_id: u32, inner: RequestInner<'a>) -> Self {
+        use RequestInner::*;
+
+        let differs = match &inner {
+            Open(_)
+            | Mkdir { .. }
+            | Stat(_)
+            | Lstat(_)
+            | Fstat(_)
+            | Setstat { .. }
+            | Fsetstat { .. } => version >= 4,
+            Rename { .. } => version >= 5,
+            Symlink { .. } => version >= 6,
+            _ => false,
+        };
+
+        if differs {
+            Self::V4 {
+                version,
+                request_id,
+                inner,
+            }
+        } else {
+            Self::V3(Request { request_id, inner })
+        }
+    }
+}
+
+impl Serialize for VersionedRequest<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use constants::*;
+        use RequestInner::*;
+
+        let (version, request_id, inner) = match self {
+            Self::V3(request) => return request.serialize(serializer),
+            Self::V4 {
+                version,
+                request_id,
+                inner,
+            } => (*version, *request_id, inner),
+        };
+
+        match inner {
+            Open(params) => {
+                let (filename, pflags, attrs) =
+                    destructure_open_request(params).map_err(S::Error::custom)?;
+
+                if version == 4 {
+                    (SSH_FXP_OPEN, request_id, filename, pflags, attrs_v4(&attrs))
+                        .serialize(serializer)
+                } else {
+                    let (desired_access, flags) = open_flags_v5(pflags);
+                    (
+                        SSH_FXP_OPEN,
+                        request_id,
+                        filename,
+                        desired_access,
+                        flags,
+                        attrs_v4(&attrs),
+                    )
+                        .serialize(serializer)
+                }
+            }
+
+            Rename { oldpath, newpath } => {
+                // flags = 0: fail if newpath already exists, same as sftp v3.
+                (SSH_FXP_RENAME, request_id, oldpath, newpath, 0_u32).serialize(serializer)
+            }
+
+            Mkdir { path, attrs } => {
+                let attrs = AttrsV4 {
+                    attrs,
+                    file_type: SSH_FILEXFER_TYPE_DIRECTORY,
+                };
+                (SSH_FXP_MKDIR, request_id, path, attrs).serialize(serializer)
+            }
+
+            Stat(path) => {
+                (SSH_FXP_STAT, request_id, path, desired_attrs(version)).serialize(serializer)
+            }
+            Lstat(path) => {
+                (SSH_FXP_LSTAT, request_id, path, desired_attrs(version)).serialize(serializer)
+            }
+            Fstat(handle) => {
+                (SSH_FXP_FSTAT, request_id, handle, desired_attrs(version)).serialize(serializer)
+            }
+
+            Setstat { path, attrs } => {
+                (SSH_FXP_SETSTAT, request_id, path, attrs_v4(attrs)).serialize(serializer)
+            }
+            Fsetstat { handle, attrs } => {
+                (SSH_FXP_FSETSTAT, request_id, handle, attrs_v4(attrs)).serialize(serializer)
+            }
+
+            Symlink {
+                linkpath,
+                targetpath,
+            } => {
+                // sym-link is a boolean, which is encoded as a single byte.
+                (SSH_FXP_LINK, request_id, linkpath, targetpath, 1_u8).serialize(serializer)
+            }
+
+            _ => Err(S::Error::custom("Request is the same as in sftp v3")),
+        }
+    }
+}
+
+/// Convert sftp v3 open flags into desired-access and flags of sftp v5.
+fn open_flags_v5(pflags: u32) -> (u32, u32) {
+    use constants::*;
+
+    let has = |flag| (pflags & flag) != 0;
+
+    let mut desired_access = 0;
+    if has(SSH_FXF_READ) {
+        desired_access |= ACE4_READ_DATA | ACE4_READ_ATTRIBUTES;
+    }
+    if has(SSH_FXF_WRITE) {
+        desired_access |= ACE4_WRITE_DATA | ACE4_WRITE_ATTRIBUTES;
+    }
+
+    let mut flags = match (has(SSH_FXF_CREAT), has(SSH_FXF_EXCL), has(SSH_FXF_TRUNC)) {
+        (true, true, _) => SSH_FXF_CREATE_NEW,
+        (true, false, true) => SSH_FXF_CREATE_TRUNCATE,
+        (true, false, false) => SSH_FXF_OPEN_OR_CREATE,
+        (false, _, true) => SSH_FXF_TRUNCATE_EXISTING,
+        (false, _, false) => SSH_FXF_OPEN_EXISTING,
+    };
+    if has(SSH_FXF_APPEND) {
+        desired_access |= ACE4_APPEND_DATA;
+        flags |= SSH_FXF_APPEND_DATA;
+    }
+
+    (desired_access, flags)
+}
+
+/// Parse ssh-encoded values one after another.
+struct Cursor<'de>(&'de [u8]);
+
+impl<'de> Cursor<'de> {
+    fn next<T: Deserialize<'de>>(&mut self) -> Result<T, Error> {
+        let (value, rest) = ssh_format::from_bytes(self.0)?;
+        self.0 = rest;
+        Ok(value)
+    }
+
+    fn next_bytes(&mut self) -> Result<&'de [u8], Error> {
+        let len: u32 = self.next()?;
+        let len = len as usize;
+
+        if self.0.len() < len {
+            return Err(ssh_format::Error::Eof.into());
+        }
+
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn next_str(&mut self) -> Result<Box<str>, Error> {
+        self.next_bytes()
+            .map(|bytes| String::from_utf8_lossy(bytes).into())
+    }
+
+    fn next_time(&mut self, subsecond: bool) -> Result<FileTime, Error> {
+        Ok(FileTime {
+            seconds: self.next()?,
+            nanoseconds: if subsecond { self.next()? } else { 0 },
+        })
+    }
+}
+
+fn file_type_to_mode(file_type: u8) -> u32 {
+    let file_type = match file_type {
+        SSH_FILEXFER_TYPE_REGULAR => FileType::RegularFile,
+        SSH_FILEXFER_TYPE_DIRECTORY => FileType::Directory,
+        SSH_FILEXFER_TYPE_SYMLINK => FileType::Symlink,
+        SSH_FILEXFER_TYPE_SOCKET => FileType::Socket,
+        SSH_FILEXFER_TYPE_CHAR_DEVICE => FileType::CharacterDevice,
+        SSH_FILEXFER_TYPE_BLOCK_DEVICE => FileType::BlockDevice,
+        SSH_FILEXFER_TYPE_FIFO => FileType::FIFO,
+        _ => return 0,
+    };
+
+    file_type as u32
+}
+
+fn parse_attrs(cursor: &mut Cursor<'_>, version: u32) -> Result<Attrs, Error> {
+    if version <= 3 {
+        return cursor.next::<FileAttrs>().map(Attrs::from);
+    }
+
+    let flags: u32 = cursor.next()?;
+    let has = |flag| (flags & flag) != 0;
+    let subsecond = has(SSH_FILEXFER_ATTR_SUBSECOND_TIMES);
+
+    let mut ext = FileAttrsExt {
+        file_type: cursor.next()?,
+        ..Default::default()
+    };
+
+    let size: Option<u64> = has(SSH_FILEXFER_ATTR_SIZE)
+        .then(|| cursor.next())
+        .transpose()?;
+
+    if has(SSH_FILEXFER_ATTR_ALLOCATION_SIZE) {
+        ext.allocation_size = Some(cursor.next()?);
+    }
+    if has(SSH_FILEXFER_ATTR_OWNERGROUP) {
+        ext.owner = Some(cursor.next_str()?);
+        ext.group = Some(cursor.next_str()?);
+    }
+
+    let permissions: Option<u32> = has(SSH_FILEXFER_ATTR_PERMISSIONS)
+        .then(|| cursor.next())
+        .transpose()?;
+
+    if has(SSH_FILEXFER_ATTR_ACCESSTIME) {
+        ext.accessed = Some(cursor.next_time(subsecond)?);
+    }
+    if has(SSH_FILEXFER_ATTR_CREATETIME) {
+        ext.created = Some(cursor.next_time(subsecond)?);
+    }
+    if has(SSH_FILEXFER_ATTR_MODIFYTIME) {
+        ext.modified = Some(cursor.next_time(subsecond)?);
+    }
+    if has(SSH_FILEXFER_ATTR_CTIME) {
+        ext.changed = Some(cursor.next_time(subsecond)?);
+    }
+    if has(SSH_FILEXFER_ATTR_ACL) {
+        ext.acl = Some(cursor.next_bytes()?.into());
+    }
+    if has(SSH_FILEXFER_ATTR_BITS) {
+        ext.attrib_bits = Some(cursor.next()?);
+        if version >= 6 {
+            ext.attrib_bits_valid = Some(cursor.next()?);
+        }
+    }
+    if has(SSH_FILEXFER_ATTR_TEXT_HINT) {
+        ext.text_hint = Some(cursor.next()?);
+    }
+    if has(SSH_FILEXFER_ATTR_MIME_TYPE) {
+        ext.mime_type = Some(cursor.next_str()?);
+    }
+    if has(SSH_FILEXFER_ATTR_LINK_COUNT) {
+        ext.link_count = Some(cursor.next()?);
+    }
+    if has(SSH_FILEXFER_ATTR_UNTRANSLATED_NAME) {
+        ext.untranslated_name = Some(cursor.next_bytes()?.into());
+    }
+    if has(SSH_FILEXFER_ATTR_EXTENDED) {
+        let count: u32 = cursor.next()?;
+        for _ in 0..count {
+            cursor.next_bytes()?;
+            cursor.next_bytes()?;
+        }
+    }
+
+    // FileAttrs can only be constructed with filetype
+    // by deserializing it in the format of sftp v3.
+    let id = ext
+        .owner
+        .as_deref()
+        .zip(ext.group.as_deref())
+        .and_then(|(owner, group)| Some((owner.parse().ok()?, group.parse().ok()?)));
+    let time = ext
+        .accessed
+        .zip(ext.modified)
+        .and_then(|(atime, mtime)| Some((to_u32(atime)?, to_u32(mtime)?)));
+
+    let mut v3_flags = 0;
+    let mut v3_attrs = Vec::new();
+
+    if let Some(size) = size {
+        v3_flags |= SSH_FILEXFER_ATTR_SIZE;
+        v3_attrs.extend_from_slice(&size.to_be_bytes());
+    }
+    if let Some((uid, gid)) = id {
+        v3_flags |= SSH_FILEXFER_ATTR_UIDGID;
+        v3_attrs.extend_from_slice(&u32::to_be_bytes(uid));
+        v3_attrs.extend_from_slice(&u32::to_be_bytes(gid));
+    }
+    if let Some(permissions) = permissions {
+        v3_flags |= SSH_FILEXFER_ATTR_PERMISSIONS;
+        let mode = (permissions & !S_IFMT) | file_type_to_mode(ext.file_type);
+        v3_attrs.extend_from_slice(&mode.to_be_bytes());
+    }
+    if let Some((atime, mtime)) = time {
+        v3_flags |= constants::SSH_FILEXFER_ATTR_ACMODTIME;
+        v3_attrs.extend_from_slice(&atime.to_be_bytes());
+        v3_attrs.extend_from_slice(&mtime.to_be_bytes());
+    }
+
+    let mut serialized = u32::to_be_bytes(v3_flags).to_vec();
+    serialized.append(&mut v3_attrs);
+
+    Ok(Attrs {
+        attrs: ssh_format::from_bytes(&serialized)?.0,
+        ext: Some(Box::new(ext)),
+    })
+}
+
+fn to_u32(time: FileTime) -> Option<u32> {
+    time.seconds.try_into().ok()
+}
+
+/// Map status codes added in sftp v4 and later to the closest [`SftpErrorKind`].
+fn status_code(code: u32) -> StatusCode {
+    use SftpErrorKind::*;
+
+    match code {
+        constants::SSH_FX_OK => StatusCode::Success,
+        constants::SSH_FX_EOF => StatusCode::Eof,
+
+        // SSH_FX_NO_SUCH_FILE, SSH_FX_NO_SUCH_PATH
+        2 | 10 => StatusCode::Failure(NoSuchFile),
+        // SSH_FX_PERMISSION_DENIED, SSH_FX_WRITE_PROTECT, SSH_FX_CANNOT_DELETE
+        3 | 12 | 22 => StatusCode::Failure(PermDenied),
+        // SSH_FX_BAD_MESSAGE, SSH_FX_INVALID_FILENAME, SSH_FX_INVALID_PARAMETER
+        5 | 20 | 23 => StatusCode::Failure(BadMessage),
+        // SSH_FX_OP_UNSUPPORTED
+        8 => StatusCode::Failure(OpUnsupported),
+        // SSH_FX_FAILURE and the other errors defined up to sftp v6, e.g.
+        // SSH_FX_FILE_ALREADY_EXISTS, SSH_FX_DIR_NOT_EMPTY, SSH_FX_QUOTA_EXCEEDED.
+        //
+        // SSH_FX_NO_CONNECTION and SSH_FX_CONNECTION_LOST are pseudo-errors
+        // meant to be generated locally, but are still reported as failures
+        // if the server returns them.
+        4 | 6 | 7 | 9 | 11 | 13..=19 | 21 | 24..=31 => StatusCode::Failure(Failure),
+
+        _ => StatusCode::Failure(Unknown),
+    }
+}
+
+/// * `bytes` - includes packet_type and request_id.
+pub(crate) fn parse_response<Buffer>(
+    bytes: &[u8],
+    version: u32,
+) -> Result<Response<Buffer>, Error> {
+    let mut cursor = Cursor(bytes);
+
+    let packet_type: u8 = cursor.next()?;
+    let _response_id: u32 = cursor.next()?;
+
+    match packet_type {
+        constants::SSH_FXP_NAME => {
+            let len: u32 = cursor.next()?;
+            let entries = (0..len)
+                .map(|_| {
+                    let filename: Box<Path> = cursor.next()?;
+                    if version <= 3 {
+                        let _longname = cursor.next_bytes()?;
+                    }
+                    let attrs = parse_attrs(&mut cursor, version)?;
+
+                    Ok(NameEntry { filename, attrs })
+                })
+                .collect::<Result<_, Error>>()?;
+
+            Ok(Response::Name(entries))
+        }
+
+        constants::SSH_FXP_ATTRS => parse_attrs(&mut cursor, version).map(Response::Attrs),
+
+        constants::SSH_FXP_STATUS if version > 3 => {
+            let code: u32 = cursor.next()?;
+            let err_msg: SftpErrMsg = cursor.next()?;
+
+            Ok(Response::Header(ResponseInner::Status {
+                status_code: status_code(code),
+                err_msg,
+            }))
+        }
+
+        _ => {
+            let response: response::Response = ssh_format::from_bytes(bytes)?.0;
+            Ok(Response::Header(response.response_inner))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Permissions;
+
+    #[test]
+    fn test_open_flags_v5() {
+        use constants::*;
+
+        assert_eq!(
+            open_flags_v5(SSH_FXF_READ),
+            (ACE4_READ_DATA | ACE4_READ_ATTRIBUTES, SSH_FXF_OPEN_EXISTING)
+        );
+        assert_eq!(
+            open_flags_v5(SSH_FXF_WRITE | SSH_FXF_CREAT | SSH_FXF_TRUNC).1,
+            SSH_FXF_CREATE_TRUNCATE
+        );
+        assert_eq!(
+            open_flags_v5(SSH_FXF_WRITE | SSH_FXF_CREAT | SSH_FXF_EXCL).1,
+            SSH_FXF_CREATE_NEW
+        );
+        assert_eq!(
+            open_flags_v5(SSH_FXF_WRITE | SSH_FXF_APPEND | SSH_FXF_CREAT),
+            (
+                ACE4_WRITE_DATA | ACE4_WRITE_ATTRIBUTES | ACE4_APPEND_DATA,
+                SSH_FXF_OPEN_OR_CREATE | SSH_FXF_APPEND_DATA
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_attrs_v6() {
+        let mut bytes = Vec::new();
+        let flags = SSH_FILEXFER_ATTR_SIZE
+            | SSH_FILEXFER_ATTR_OWNERGROUP
+            | SSH_FILEXFER_ATTR_PERMISSIONS
+            | SSH_FILEXFER_ATTR_ACCESSTIME
+            | SSH_FILEXFER_ATTR_MODIFYTIME
+            | SSH_FILEXFER_ATTR_SUBSECOND_TIMES
+            | SSH_FILEXFER_ATTR_LINK_COUNT;
+        bytes.extend_from_slice(&flags.to_be_bytes());
+        bytes.push(SSH_FILEXFER_TYPE_DIRECTORY);
+        bytes.extend_from_slice(&2333_u64.to_be_bytes());
+        for name in ["1000", "staff"] {
+            bytes.extend_from_slice(&(name.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+        }
+        bytes.extend_from_slice(&0o755_u32.to_be_bytes());
+        for (seconds, nanoseconds) in [(2_i64, 3_u32), (150, 0)] {
+            bytes.extend_from_slice(&seconds.to_be_bytes());
+            bytes.extend_from_slice(&nanoseconds.to_be_bytes());
+        }
+        bytes.extend_from_slice(&3_u32.to_be_bytes());
+
+        let attrs = parse_attrs(&mut Cursor(&bytes), 6).unwrap();
+
+        assert_eq!(attrs.get_size(), Some(2333));
+        // gid is not numeric
+        assert_eq!(attrs.get_id(), None);
+        assert_eq!(
+            attrs.get_permissions(),
+            Some(Permissions::from_bits_truncate(0o755))
+        );
+        assert_eq!(attrs.get_filetype(), Some(FileType::Directory));
+        assert_eq!(
+            attrs.get_time().map(|(a, m)| (a.into_raw(), m.into_raw())),
+            Some((2, 150))
+        );
+
+        let ext = attrs.ext.unwrap();
+        assert_eq!(ext.owner.as_deref(), Some("1000"));
+        assert_eq!(ext.group.as_deref(), Some("staff"));
+        assert_eq!(
+            ext.accessed,
+            Some(FileTime {
+                seconds: 2,
+                nanoseconds: 3
+            })
+        );
+        assert_eq!(ext.link_count, Some(3));
+        assert_eq!(ext.created, None);
+    }
+
+    #[test]
+    fn test_status_code() {
+        assert!(matches!(
+            status_code(10),
+            StatusCode::Failure(SftpErrorKind::NoSuchFile)
+        ));
+        assert!(matches!(
+            status_code(11),
+            StatusCode::Failure(SftpErrorKind::Failure)
+        ));
+        assert!(matches!(
+            status_code(7),
+            StatusCode::Failure(SftpErrorKind::Failure)
+        ));
+        assert!(matches!(
+            status_code(100),
+            StatusCode::Failure(SftpErrorKind::Unknown)
+        ));
+    }
+
+    #[test]
+    fn test_serialize_setstat_v4() {
+        let mut attrs = FileAttrs::new();
+        attrs.set_size(1);
+
+        let serialized = ssh_format::to_bytes(&(
+            constants::SSH_FXP_SETSTAT,
+            0_u32,
+            Path::new("a"),
+            attrs_v4(&attrs),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            &serialized[4..],
+            [
+                constants::SSH_FXP_SETSTAT,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                1,
+                b'a',
+                0,
+                0,
+                0,
+                SSH_FILEXFER_ATTR_SIZE as u8,
+                SSH_FILEXFER_TYPE_UNKNOWN,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                1
+            ]
+        );
+
+        attrs.set_id(0, 0);
+        ssh_format::to_bytes(&attrs_v4(&attrs)).unwrap_err();
+    }
+}
@@ -4,6 +4,7 @@ use super::*;
 
 use awaitable_responses::ArenaArc;
 use connection::SharedData;
+use versioned::VersionedRequest;
 
 use std::{
     borrow::Cow,
@@ -16,7 +17,7 @@ use std::{
 
 use bytes::{BufMut, Bytes, BytesMut};
 use openssh_sftp_protocol::{
-    file_attrs::FileAttrs, request::*, serde::Serialize, ssh_format::Serializer, Handle,
+    constants, file_attrs::FileAttrs, request::*, serde::Serialize, ssh_format::Serializer, Handle,
 };
 
 /// It is recommended to create at most one `WriteEnd` per thread
@@ -106,12 +107,10 @@ where
         request: RequestInner<'_>,
         buffer: Option<Buffer>,
     ) -> Result<ArenaArc<Buffer>, Error> {
+        let version = self.shared_data.sftp_version();
         let serialized = Self::serialize(
             &mut self.serializer,
-            Request {
-                request_id: ArenaArc::slot(&id.0),
-                inner: request,
-            },
+            VersionedRequest::new(version, ArenaArc::slot(&id.0), request),
         )?;
 
         id.0.reset(buffer);
@@ -120,6 +119,35 @@ where
         Ok(id.into_inner())
     }
 
+    /// Send extended requests that are not covered by [`RequestInner`].
+    ///
+    /// NOTE that this merely add the request to the buffer, you need to call
+    /// [`SharedData::flush`] to actually send the requests.
+    fn send_extended_request_impl<T>(
+        &mut self,
+        id: Id<Buffer>,
+        name: &str,
+        payload: T,
+    ) -> Result<ArenaArc<Buffer>, Error>
+    where
+        T: Serialize,
+    {
+        let serialized = Self::serialize(
+            &mut self.serializer,
+            (
+                constants::SSH_FXP_EXTENDED,
+                ArenaArc::slot(&id.0),
+                name,
+                payload,
+            ),
+        )?;
+
+        id.0.reset(None);
+        self.shared_data.queue().push(serialized);
+
+        Ok(id.into_inner())
+    }
+
     pub fn send_open_file_request(
         &mut self,
         id: Id<Buffer>,
@@ -279,6 +307,22 @@ where
             .map(AwaitableStatus::new)
     }
 
+    /// Same as [`WriteEnd::send_setstat_request`], except that it does not
+    /// follow symlink.
+    ///
+    /// # Precondition
+    ///
+    /// Requires `extensions::contains(Extensions::LSETSTAT)` to be true.
+    pub fn send_lsetstat_request(
+        &mut self,
+        id: Id<Buffer>,
+        path: Cow<'_, Path>,
+        attrs: FileAttrs,
+    ) -> Result<AwaitableStatus<Buffer>, Error> {
+        self.send_request(id, RequestInner::Lsetstat(path, attrs), None)
+            .map(AwaitableStatus::new)
+    }
+
     pub fn send_readlink_request(
         &mut self,
         id: Id<Buffer>,
@@ -435,6 +479,228 @@ where
         )
         .map(AwaitableStatus::new)
     }
+
+    /// Return statistics of the filesystem containing `path`.
+    ///
+    /// # Precondition
+    ///
+    /// Requires `extensions::contains(Extensions::STATVFS)` to be true.
+    pub fn send_statvfs_request(
+        &mut self,
+        id: Id<Buffer>,
+        path: Cow<'_, Path>,
+    ) -> Result<AwaitableFsStats<Buffer>, Error> {
+        self.send_extended_request_impl(id, constants::EXT_NAME_STATVFS.0, path)
+            .map(AwaitableFsStats::new)
+    }
+
+    /// Return statistics of the filesystem containing the file
+    /// specified by `handle`.
+    ///
+    /// # Precondition
+    ///
+    /// Requires `extensions::contains(Extensions::FSTATVFS)` to be true.
+    pub fn send_fstatvfs_request(
+        &mut self,
+        id: Id<Buffer>,
+        handle: Cow<'_, Handle>,
+    ) -> Result<AwaitableFsStats<Buffer>, Error> {
+        self.send_extended_request_impl(id, constants::EXT_NAME_FSTATVFS.0, handle)
+            .map(AwaitableFsStats::new)
+    }
+
+    /// Resolve `uids` and `gids` to user and group names.
+    ///
+    /// The reply contains one name for each id, in the same order,
+    /// with an empty name for any id the server fails to resolve.
+    ///
+    /// # Precondition
+    ///
+    /// Requires the server to support the `users-groups-by-id@openssh.com`
+    /// extension, which can be checked with [`SharedData::server_extension_data`].
+    pub fn send_users_groups_by_id_request(
+        &mut self,
+        id: Id<Buffer>,
+        uids: &[u32],
+        gids: &[u32],
+    ) -> Result<AwaitableUsersGroups<Buffer>, Error> {
+        fn pack(ids: &[u32]) -> Vec<u8> {
+            ids.iter().flat_map(|id| id.to_be_bytes()).collect()
+        }
+
+        self.send_extended_request_impl(
+            id,
+            "users-groups-by-id@openssh.com",
+            (&*pack(uids), &*pack(gids)),
+        )
+        .map(AwaitableUsersGroups::new)
+    }
+
+    /// Return the home directory of `username`.
+    ///
+    /// If `username` is empty, then the home directory of the user
+    /// the `sftp-server` runs as is returned.
+    ///
+    /// # Precondition
+    ///
+    /// Requires the server to support the `home-directory` extension,
+    /// which can be checked with [`SharedData::server_extension_data`].
+    pub fn send_home_directory_request(
+        &mut self,
+        id: Id<Buffer>,
+        username: &str,
+    ) -> Result<AwaitableName<Buffer>, Error> {
+        self.send_extended_request_impl(id, "home-directory", username)
+            .map(AwaitableName::new)
+    }
+
+    /// Return hashes of the file specified by `handle`.
+    ///
+    /// * `algorithms` - comma-separated list of hash algorithms, the server
+    ///   uses the first one it supports.
+    /// * `length` - `0` means up to the end of the file.
+    /// * `block_size` - `0` means one hash for the whole range, otherwise
+    ///   it must be at least `256`.
+    ///
+    /// The file must be opened for reading.
+    ///
+    /// # Precondition
+    ///
+    /// Requires the server to support the `check-file` extension, which can
+    /// be checked with [`SharedData::server_extension_data`].
+    pub fn send_check_file_handle_request(
+        &mut self,
+        id: Id<Buffer>,
+        handle: Cow<'_, Handle>,
+        algorithms: &str,
+        offset: u64,
+        length: u64,
+        block_size: u32,
+    ) -> Result<AwaitableFileChecksum<Buffer>, Error> {
+        self.send_extended_request_impl(
+            id,
+            "check-file-handle",
+            (handle, algorithms, offset, length, block_size),
+        )
+        .map(AwaitableFileChecksum::new)
+    }
+
+    /// Same as [`WriteEnd::send_check_file_handle_request`], except that
+    /// the file is specified by `path`.
+    ///
+    /// # Precondition
+    ///
+    /// Requires the server to support the `check-file` extension, which can
+    /// be checked with [`SharedData::server_extension_data`].
+    pub fn send_check_file_name_request(
+        &mut self,
+        id: Id<Buffer>,
+        path: Cow<'_, Path>,
+        algorithms: &str,
+        offset: u64,
+        length: u64,
+        block_size: u32,
+    ) -> Result<AwaitableFileChecksum<Buffer>, Error> {
+        self.send_extended_request_impl(
+            id,
+            "check-file-name",
+            (path, algorithms, offset, length, block_size),
+        )
+        .map(AwaitableFileChecksum::new)
+    }
+
+    /// Return md5 hash of the file specified by `handle`.
+    ///
+    /// * `length` - `0` means up to the end of the file.
+    /// * `quick_check_hash` - if not empty, the server only hashes the range if
+    ///   the md5 hash of the first 2048 bytes of it matches `quick_check_hash`,
+    ///   otherwise an empty hash is returned.
+    ///
+    /// The file must be opened for reading.
+    ///
+    /// # Precondition
+    ///
+    /// Requires the server to support the `md5-hash-handle` extension, which can
+    /// be checked with [`SharedData::server_extension_data`].
+    pub fn send_md5_hash_handle_request(
+        &mut self,
+        id: Id<Buffer>,
+        handle: Cow<'_, Handle>,
+        offset: u64,
+        length: u64,
+        quick_check_hash: &[u8],
+    ) -> Result<AwaitableMd5Hash<Buffer>, Error> {
+        self.send_extended_request_impl(
+            id,
+            "md5-hash-handle",
+            (handle, offset, length, quick_check_hash),
+        )
+        .map(AwaitableMd5Hash::new)
+    }
+
+    /// Same as [`WriteEnd::send_md5_hash_handle_request`], except that
+    /// the file is specified by `path`.
+    ///
+    /// # Precondition
+    ///
+    /// Requires the server to support the `md5-hash` extension, which can
+    /// be checked with [`SharedData::server_extension_data`].
+    pub fn send_md5_hash_request(
+        &mut self,
+        id: Id<Buffer>,
+        path: Cow<'_, Path>,
+        offset: u64,
+        length: u64,
+        quick_check_hash: &[u8],
+    ) -> Result<AwaitableMd5Hash<Buffer>, Error> {
+        self.send_extended_request_impl(id, "md5-hash", (path, offset, length, quick_check_hash))
+            .map(AwaitableMd5Hash::new)
+    }
+
+    /// Copy the whole file `src` to `dst` on the server.
+    ///
+    /// If `overwrite` is `false` and `dst` already exists,
+    /// the request fails.
+    ///
+    /// # Precondition
+    ///
+    /// Requires the server to support the `copy-file` extension, which can
+    /// be checked with [`SharedData::server_extension_data`].
+    pub fn send_copy_file_request(
+        &mut self,
+        id: Id<Buffer>,
+        src: Cow<'_, Path>,
+        dst: Cow<'_, Path>,
+        overwrite: bool,
+    ) -> Result<AwaitableStatus<Buffer>, Error> {
+        // ssh_format serializes bool as uint32, while the
+        // sftp protocol expects a single byte.
+        self.send_extended_request_impl(id, "copy-file", (src, dst, u8::from(overwrite)))
+            .map(AwaitableStatus::new)
+    }
+
+    /// Send an arbitrary extended request `name` with `payload`, which
+    /// allows using extensions not covered by other `send_*_request`.
+    ///
+    /// `payload` is serialized in the ssh format right after `name`,
+    /// e.g. `(path, 0_u64)` is serialized as a string followed by an uint64.
+    ///
+    /// # Precondition
+    ///
+    /// Requires the server to support extension `name`, otherwise
+    /// the server is expected to respond with [`SftpErrorKind::OpUnsupported`].
+    pub fn send_extended_request<T>(
+        &mut self,
+        id: Id<Buffer>,
+        name: &str,
+        payload: T,
+    ) -> Result<AwaitableExtendedReply<Buffer>, Error>
+    where
+        T: Serialize,
+    {
+        self.send_extended_request_impl(id, name, payload)
+            .map(AwaitableExtendedReply::new)
+    }
 }
 
 impl<Buffer, Q, Auxiliary> WriteEnd<Buffer, Q, Auxiliary>
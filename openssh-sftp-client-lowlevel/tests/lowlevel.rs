@@ -623,6 +623,59 @@ async fn test_setstat() {
     assert!(child.wait().await.unwrap().success());
 }
 
+#[tokio::test]
+async fn test_lsetstat() {
+    let (mut write_end, mut read_end, mut child, extensions) = connect_with_extensions().await;
+    assert!(extensions.contains(lowlevel::Extensions::LSETSTAT));
+
+    let id = write_end.create_response_id();
+
+    let tempdir = create_tmpdir();
+    let filename = tempdir.path().join("file");
+    let linkname = tempdir.path().join("symlink");
+
+    fs::File::create(&filename).unwrap().set_len(2000).unwrap();
+    symlink(&filename, &linkname).unwrap();
+
+    let file_modified = fs::metadata(&filename).unwrap().modified().unwrap();
+
+    let timestamp = UnixTimeStamp::from_raw(1000).unwrap();
+
+    let mut fileattrs = FileAttrs::default();
+    fileattrs.set_time(timestamp, timestamp);
+
+    // lsetstat
+    let awaitable = write_end
+        .send_lsetstat_request(id, Cow::Borrowed(&linkname), fileattrs)
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let id = awaitable.wait().await.unwrap().0;
+
+    // lstat
+    let awaitable = write_end
+        .send_lstat_request(id, Cow::Borrowed(&linkname))
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, attrs) = awaitable.wait().await.unwrap();
+
+    assert_eq!(attrs.get_filetype().unwrap(), FileType::Symlink);
+    assert_eq!(attrs.get_time().unwrap().1, timestamp);
+
+    // The target of the symlink is left untouched
+    assert_eq!(
+        fs::metadata(&filename).unwrap().modified().unwrap(),
+        file_modified
+    );
+
+    drop(id);
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
+
 #[tokio::test]
 async fn test_fsetstat() {
     let (mut write_end, mut read_end, mut child) = connect().await;
@@ -994,3 +1047,291 @@ async fn test_ext_copy_data() {
 
     assert!(child.wait().await.unwrap().success());
 }
+
+#[tokio::test]
+async fn test_statvfs() {
+    let (mut write_end, mut read_end, mut child, extensions) = connect_with_extensions().await;
+    assert!(extensions.contains(lowlevel::Extensions::STATVFS));
+
+    let id = write_end.create_response_id();
+
+    let tempdir = create_tmpdir();
+
+    let awaitable = write_end
+        .send_statvfs_request(id, Cow::Borrowed(tempdir.path()))
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, stats) = awaitable.wait().await.unwrap();
+
+    eprintln!("{:#?}", stats);
+
+    assert!(stats.blocks >= stats.blocks_free);
+    assert!(stats.blocks_free >= stats.blocks_avail);
+
+    drop(id);
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn test_extended_request() {
+    let (mut write_end, mut read_end, mut child) = connect().await;
+
+    let id = write_end.create_response_id();
+
+    let tempdir = create_tmpdir();
+    let filename = tempdir.path().join("file");
+    let linkname = tempdir.path().join("hardlink");
+
+    fs::File::create(&filename).unwrap();
+
+    // Extended reply
+    let awaitable = write_end
+        .send_extended_request(id, "statvfs@openssh.com", tempdir.path())
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, reply) = awaitable.wait().await.unwrap();
+
+    let stats: FsStats = reply.deserialize().unwrap();
+    assert!(stats.blocks >= stats.blocks_free);
+
+    // Status
+    let awaitable = write_end
+        .send_extended_request(
+            id,
+            "hardlink@openssh.com",
+            (filename.as_path(), linkname.as_path()),
+        )
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, reply) = awaitable.wait().await.unwrap();
+
+    assert!(matches!(reply, ExtendedReply::Success), "{:#?}", reply);
+    assert!(reply.deserialize::<u32>().is_err());
+    assert!(linkname.exists());
+
+    // Unsupported extension
+    let awaitable = write_end
+        .send_extended_request(id, "no-such-extension@example.com", ())
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let err = awaitable.wait().await.unwrap_err();
+    assert!(
+        matches!(err, Error::SftpError(SftpErrorKind::OpUnsupported, _)),
+        "{:#?}",
+        err
+    );
+
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn test_fstatvfs() {
+    let (mut write_end, mut read_end, mut child, extensions) = connect_with_extensions().await;
+    assert!(extensions.contains(lowlevel::Extensions::FSTATVFS));
+
+    let id = write_end.create_response_id();
+
+    let tempdir = create_tmpdir();
+    let filename = tempdir.path().join("file");
+    fs::File::create(&filename).unwrap();
+
+    // open
+    let awaitable = write_end
+        .send_open_file_request(id, OpenFileRequest::open(Cow::Borrowed(&filename)))
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, handle) = awaitable.wait().await.unwrap();
+
+    // fstatvfs
+    let awaitable = write_end
+        .send_fstatvfs_request(id, Cow::Borrowed(&handle))
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, fstats) = awaitable.wait().await.unwrap();
+
+    // statvfs on the same file should return the same fsid
+    let awaitable = write_end
+        .send_statvfs_request(id, Cow::Borrowed(&filename))
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, stats) = awaitable.wait().await.unwrap();
+
+    assert_eq!(stats.fsid, fstats.fsid);
+
+    drop(id);
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn test_users_groups_by_id() {
+    use std::os::unix::fs::MetadataExt;
+
+    let (mut write_end, mut read_end, mut child, _extensions) = connect_with_extensions().await;
+
+    let id = write_end.create_response_id();
+
+    let tempdir = create_tmpdir();
+    let metadata = fs::metadata(tempdir.path()).unwrap();
+
+    let awaitable = write_end
+        .send_users_groups_by_id_request(id, &[metadata.uid()], &[metadata.gid()])
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, names) = awaitable.wait().await.unwrap();
+
+    eprintln!("{:#?}", names);
+
+    assert_eq!(names.users.len(), 1);
+    assert_eq!(names.groups.len(), 1);
+    assert!(!names.users[0].is_empty());
+    assert!(!names.groups[0].is_empty());
+
+    drop(id);
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn test_server_extension_data() {
+    let (write_end, read_end, mut child) = connect().await;
+
+    assert_eq!(
+        write_end.server_extension_data("posix-rename@openssh.com"),
+        Some(&b"1"[..])
+    );
+    assert_eq!(write_end.server_extension_data("check-file"), None);
+
+    assert_eq!(write_end.server_version(), Some(3));
+    assert!(write_end
+        .server_extensions()
+        .any(|extension| extension == ("limits@openssh.com", &b"1"[..])));
+
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn test_check_file_unsupported() {
+    let (mut write_end, mut read_end, mut child) = connect().await;
+
+    let id = write_end.create_response_id();
+
+    let tempdir = create_tmpdir();
+    let filename = tempdir.path().join("file");
+
+    fs::File::create(&filename).unwrap().set_len(2000).unwrap();
+
+    // openssh does not support check-file
+    let awaitable = write_end
+        .send_check_file_name_request(id, Cow::Borrowed(&filename), "md5,sha1", 0, 0, 0)
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let err = awaitable.wait().await.unwrap_err();
+    assert!(
+        matches!(err, Error::SftpError(SftpErrorKind::OpUnsupported, _)),
+        "{:#?}",
+        err
+    );
+
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn test_connect_with_version() {
+    let (mut child, stdin, stdout) = launch_sftp().await;
+
+    let buffer_size = NonZeroUsize::new(1000).unwrap();
+
+    let mut write_end = lowlevel::connect_with_version(
+        MpscQueue::default(),
+        Mutex::new(stdin),
+        lowlevel::SFTP_MAX_VERSION,
+    )
+    .unwrap();
+
+    let mut read_end = ReadEnd::new(stdout, buffer_size, write_end.deref().clone());
+
+    flush(&mut read_end).await;
+    read_end.receive_server_hello().await.unwrap();
+
+    // openssh only speaks sftp v3
+    assert_eq!(write_end.sftp_version(), 3);
+
+    let id = write_end.create_response_id();
+
+    let tempdir = create_tmpdir();
+    let filename = tempdir.path().join("file");
+
+    fs::File::create(&filename).unwrap().set_len(2000).unwrap();
+
+    let awaitable = write_end
+        .send_stat_request(id, Cow::Borrowed(&filename))
+        .unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, attrs) = awaitable.wait().await.unwrap();
+
+    assert_eq!(attrs.get_size().unwrap(), 2000);
+    assert!(attrs.ext.is_none());
+
+    drop(id);
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+async fn test_connect_with_invalid_version() {
+    assert!(matches!(
+        lowlevel::connect_with_version::<Vec<u8>, _, _>(MpscQueue::default(), (), 7),
+        Err(Error::UnsupportedSftpProtocol { version: 7 })
+    ));
+}
+
+#[tokio::test]
+async fn test_home_directory() {
+    let (mut write_end, mut read_end, mut child, _extensions) = connect_with_extensions().await;
+
+    let id = write_end.create_response_id();
+
+    let awaitable = write_end.send_home_directory_request(id, "").unwrap();
+
+    read_one_packet(&mut read_end).await;
+    let (id, path) = awaitable.wait().await.unwrap();
+
+    eprintln!("{:#?}", path);
+
+    assert!(path.is_absolute());
+
+    drop(id);
+    drop(write_end);
+    drop(read_end);
+
+    assert!(child.wait().await.unwrap().success());
+}
@@ -1,6 +1,6 @@
 #![forbid(unsafe_code)]
 
-use std::{io, num::TryFromIntError, process::ExitStatus};
+use std::{fmt, io, num::TryFromIntError, path::PathBuf, process::ExitStatus};
 
 pub use awaitable_error::Error as AwaitableError;
 pub use openssh_sftp_protocol_error::{
@@ -15,10 +15,13 @@ use thiserror::Error as ThisError;
 #[non_exhaustive]
 #[derive(Debug, ThisError)]
 pub enum Error {
-    /// Server speaks sftp protocol other than protocol 3.
-    #[error("Server does not support sftp protocol v3: It only support sftp protocol newer than {version}.")]
+    /// Server speaks sftp protocol older than protocol 3, or the requested
+    /// sftp protocol version is not supported by this crate.
+    #[error(
+        "Unsupported sftp protocol version {version}: only sftp protocol v3 to v6 is supported."
+    )]
     UnsupportedSftpProtocol {
-        /// The minimal sftp protocol version the server supported.
+        /// The unsupported sftp protocol version.
         version: u32,
     },
 
@@ -83,6 +86,11 @@ pub enum Error {
     #[error(transparent)]
     RecursiveErrors3(Box<RecursiveError3>),
 
+    /// Some of the paths could not be removed when removing a directory
+    /// recursively.
+    #[error(transparent)]
+    RemoveDirAll(Box<RemoveDirAllError>),
+
     /// Sftp server error
     #[error("Sftp server reported error kind {0:#?}, msg: {1}")]
     SftpError(SftpErrorKind, SftpErrMsg),
@@ -132,3 +140,25 @@ pub struct RecursiveError3 {
     #[source]
     pub err3: Error,
 }
+
+/// Paths that could not be removed when removing a directory recursively.
+#[derive(Debug)]
+pub struct RemoveDirAllError {
+    /// Paths that could not be removed, along with the error
+    /// returned for each of them.
+    pub failures: Vec<(PathBuf, Error)>,
+}
+
+impl fmt::Display for RemoveDirAllError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to remove {} paths", self.failures.len())?;
+
+        for (path, err) in &self.failures {
+            write!(f, ", {}: {}", path.display(), err)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for RemoveDirAllError {}
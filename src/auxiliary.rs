@@ -1,21 +1,49 @@
-use crate::{lowlevel::Extensions, SftpAuxiliaryData};
+use crate::{lowlevel::Extensions, rate_limit::RateLimiter, SftpAuxiliaryData};
 
-use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use once_cell::sync::OnceCell;
-use tokio::{runtime::Handle, sync::Notify};
+use tokio::{
+    runtime::Handle,
+    sync::{Notify, Semaphore},
+};
 use tokio_util::sync::CancellationToken;
 
+/// Number of permits of `ConnInfo::handle_semaphore` if the number of
+/// open handles is unlimited.
+///
+/// It is less than `Semaphore::MAX_PERMITS` on all platforms.
+pub(super) const UNLIMITED_OPEN_HANDLES: usize = (u32::MAX >> 3) as usize;
+
 #[derive(Debug, Copy, Clone)]
 pub(super) struct Limits {
     pub(super) read_len: u32,
     pub(super) write_len: u32,
+    /// `None` if unlimited.
+    pub(super) open_handles: Option<usize>,
 }
 
 #[derive(Debug)]
 pub(super) struct ConnInfo {
     pub(super) limits: Limits,
     pub(super) extensions: Extensions,
+    /// Each open handle holds one permit until it is closed.
+    pub(super) handle_semaphore: Arc<Semaphore>,
+}
+
+/// Names resolved via the `users-groups-by-id` extension.
+///
+/// `None` means that the server failed to resolve the id.
+#[derive(Debug, Default)]
+pub(super) struct NamesCache {
+    pub(super) users: HashMap<u32, Option<Box<str>>>,
+    pub(super) groups: HashMap<u32, Option<Box<str>>>,
 }
 
 #[derive(Debug)]
@@ -56,6 +84,14 @@ pub(super) struct Auxiliary {
 
     pub(super) tokio_compat_file_write_limit: usize,
 
+    /// `None` if caching of user/group names is disabled.
+    pub(super) names_cache: Option<Mutex<NamesCache>>,
+
+    /// Limits bytes sent by flush_task.
+    pub(super) upload_limiter: RateLimiter,
+    /// Limits bytes received by read_task.
+    pub(super) download_limiter: RateLimiter,
+
     pub(super) tokio_handle: Handle,
 }
 
@@ -64,6 +100,7 @@ impl Auxiliary {
         max_pending_requests: u16,
         auxiliary_data: SftpAuxiliaryData,
         tokio_compat_file_write_limit: usize,
+        cache_names: bool,
         tokio_handle: Handle,
     ) -> Self {
         Self {
@@ -87,6 +124,11 @@ impl Auxiliary {
 
             tokio_compat_file_write_limit,
 
+            names_cache: cache_names.then(Mutex::default),
+
+            upload_limiter: RateLimiter::new(),
+            download_limiter: RateLimiter::new(),
+
             tokio_handle,
         }
     }
@@ -123,6 +165,18 @@ impl Auxiliary {
         self.conn_info().limits
     }
 
+    pub(super) fn handle_semaphore(&self) -> &Arc<Semaphore> {
+        &self.conn_info().handle_semaphore
+    }
+
+    /// Return number of handles currently open.
+    pub(super) fn open_handles(&self) -> usize {
+        let limits = self.limits();
+        let max_open_handles = limits.open_handles.unwrap_or(UNLIMITED_OPEN_HANDLES);
+
+        max_open_handles - self.handle_semaphore().available_permits()
+    }
+
     pub(super) fn max_pending_requests(&self) -> usize {
         self.max_pending_requests as usize
     }
@@ -168,6 +222,18 @@ impl Auxiliary {
         self.tokio_compat_file_write_limit
     }
 
+    pub(super) fn names_cache(&self) -> Option<&Mutex<NamesCache>> {
+        self.names_cache.as_ref()
+    }
+
+    pub(super) fn upload_limiter(&self) -> &RateLimiter {
+        &self.upload_limiter
+    }
+
+    pub(super) fn download_limiter(&self) -> &RateLimiter {
+        &self.download_limiter
+    }
+
     pub(super) fn tokio_handle(&self) -> &Handle {
         &self.tokio_handle
     }
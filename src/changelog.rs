@@ -1,7 +1,92 @@
 #[allow(unused_imports)]
 use crate::*;
 
-#[doc(hidden)]
+/// ## Added
+///  - [`fs::Fs::statvfs`] and [`file::File::statvfs`] to query statistics
+///    of the remote filesystem, returned as [`fs::FsStats`]
+///  - [`Sftp::support_statvfs`] and [`Sftp::support_fstatvfs`] to check if
+///    the server supports the statvfs and fstatvfs extensions
+///  - [`fs::Fs::set_symlink_metadata`] to change the metadata of a symlink
+///    without following it
+///  - [`Sftp::support_lsetstat`] to check if the server supports the lsetstat extension
+///  - [`fs::Fs::lookup_names`] to resolve uids and gids to user and group names
+///    using the users-groups-by-id extension
+///  - [`SftpOptions::cache_names`] to cache names resolved by [`fs::Fs::lookup_names`]
+///  - [`Sftp::support_users_groups_by_id`] to check if the server supports
+///    the users-groups-by-id extension
+///  - [`fs::Fs::home_dir`] to find the home directory of a user using the
+///    home-directory extension, with the method used reported as [`fs::HomeDirMethod`]
+///  - [`Sftp::support_home_directory`] to check if the server supports
+///    the home-directory extension
+///  - [`SftpOptions::max_sftp_version`] to negotiate sftp v4 to v6 with the server
+///    and [`Sftp::sftp_version`] to get the negotiated version
+///  - [`metadata::MetaData::owner`], [`metadata::MetaData::group`],
+///    [`metadata::MetaData::created`], [`metadata::MetaData::changed`],
+///    [`metadata::MetaData::acl`] and other getters for attributes only
+///    available in sftp v4 and later
+///  - [`fs::Fs::checksum`] and [`file::File::checksum`] to calculate hashes of
+///    a remote file on the server using the check-file or md5-hash extensions,
+///    returned as [`fs::Checksum`]
+///  - [`Sftp::checksum_algorithms`] to get the hash algorithms supported by the server
+///  - [`Sftp::server_version`], [`Sftp::server_extensions`] and
+///    [`Sftp::server_extension_data`] to inspect the hello message of the server
+///  - [`SftpOptions::max_open_handles`] to limit the number of open files and
+///    directories, which defaults to the limit returned by the server
+///  - [`Sftp::max_open_handles`] and [`Sftp::open_handles`]
+///  - [`fs::Fs::copy`] and [`fs::Fs::copy_with_permissions`] to copy a file on
+///    the remote using the copy-file or copy-data extension, or pipelined read
+///    and write, with the method used reported as [`fs::CopyMethod`]
+///  - [`fs::Fs::walk_dir`] to recursively walk a remote tree using
+///    [`fs::WalkDir`] and [`fs::WalkDirStream`], which read multiple
+///    directories at the same time
+///  - [`fs::Fs::remove_dir_all`] to remove a directory and all its contents
+///    using pipelined requests
+///  - [`Error::RemoveDirAll`] and [`error::RemoveDirAllError`] listing the paths
+///    that could not be removed by [`fs::Fs::remove_dir_all`]
+///  - [`fs::Fs::create_dir_all`] and [`fs::DirBuilder::recursive`] to create
+///    a directory along with all its missing parents
+///  - [`fs::Fs::upload_dir`] and [`fs::Uploader`] to upload a local tree to
+///    the remote, returning a [`fs::TransferSummary`]
+///  - [`fs::Fs::download_dir`] and [`fs::Downloader`] to download a remote
+///    tree to local
+///  - `From<Permissions> for u16` to convert [`metadata::Permissions`] back
+///    to numeric file mode bits
+///  - [`fs::Fs::glob`] to find remote paths matching a glob pattern,
+///    returned as [`fs::GlobStream`]
+///  - [`fs::Fs::reput`] and [`fs::Fs::reget`] to resume interrupted uploads and
+///    downloads of a file, checking the existing part as set by [`fs::ResumeCheck`]
+///  - [`fs::Fs::write_atomic`], [`fs::Fs::create_atomic`] and [`fs::AtomicFile`] to
+///    replace a file atomically by writing to a temporary file and renaming it
+///    using the posix-rename extension
+///  - [`file::File::read_all_into`] to read a file into an `AsyncWrite` keeping
+///    multiple read requests in flight, and [`fs::Fs::download_file`] using it
+///    to download a file
+///  - [`file::File::write_all_from`] to write data from an `AsyncRead` into a file
+///    keeping multiple write requests in flight, and [`fs::Fs::upload_file`] using
+///    it to upload a file
+///  - [`Progress`], [`ProgressObserver`] and [`TransferProgress`] to report bytes
+///    transferred, the total and the current rate, which can be attached using
+///    [`file::File::set_progress`], [`fs::Uploader::progress`] and
+///    [`fs::Downloader::progress`]
+///  - [`SftpOptions::upload_rate_limit`] and [`SftpOptions::download_rate_limit`]
+///    to limit bytes sent and received per second, which can be changed at
+///    runtime via [`Sftp::set_upload_rate_limit`] and [`Sftp::set_download_rate_limit`]
+///  - [`file::File::read_at`], [`file::File::write_at`], [`file::File::write_vectorized_at`]
+///    and [`file::File::write_zero_copy_at`] to read and write at an offset using
+///    `&self` without changing the offset of the file
+///  - [`file::File::into_stream`] returning [`file::FileStream`], a stream of the
+///    buffers read from the file keeping multiple read requests in flight
+///  - [`file::File::into_sink`] returning [`file::FileSink`], a sink writing
+///    [`bytes::Bytes`] into the file without copying them, or `buf_list::BufList`
+///    with the new feature `buf-list`
+///
+/// ## Changed
+///  - [`metadata::MetaData`] no longer implements `Copy`
+///  - [`file::OpenOptions::open`] and [`fs::Fs::open_dir`] wait for a free slot
+///    if the maximum number of open handles is reached
+///  - Bump dependency `openssh-sftp-client-lowlevel` to v0.7.0.
+///  - Bump dependency `openssh-sftp-error` to v0.5.0, which adds
+///    [`Error::RemoveDirAll`] and [`error::RemoveDirAllError`].
 pub mod unreleased {}
 
 /// # Added
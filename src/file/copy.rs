@@ -0,0 +1,279 @@
+use super::File;
+use crate::{
+    lowlevel::{self, Data},
+    Error,
+};
+
+use std::{borrow::Cow, cmp::min, collections::VecDeque, num::NonZeroUsize};
+
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+type AwaitableDataFuture = lowlevel::AwaitableDataFuture<crate::Buffer>;
+type AwaitableStatusFuture = lowlevel::AwaitableStatusFuture<crate::Buffer>;
+
+impl File {
+    /// Copy data from `self` to `dst` until EOF is encountered, using
+    /// read and write requests with up to
+    /// [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`] of each
+    /// in flight at the same time.
+    ///
+    /// After a successful function call, the offset of `self` and `dst`
+    /// are unchanged.
+    ///
+    /// Return number of bytes copied.
+    pub(crate) async fn copy_all_to_pipelined(&mut self, dst: &mut File) -> Result<u64, Error> {
+        self.check_for_readable()?;
+        dst.check_for_writable()?;
+
+        let max_requests = lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS;
+        let read_len = min(self.max_read_len_impl(), dst.max_write_len_impl());
+
+        // Writes are positional, so short reads can simply be retried
+        // out of order.
+        let mut reads: VecDeque<(u64, u32, AwaitableDataFuture)> = VecDeque::new();
+        let mut writes: VecDeque<AwaitableStatusFuture> = VecDeque::new();
+
+        let mut read_offset = self.offset;
+        let mut pending_retries: Vec<(u64, u32)> = Vec::new();
+        let mut eof = false;
+        let mut copied = 0;
+
+        loop {
+            while reads.len() < max_requests {
+                let (offset, len) = if let Some(retry) = pending_retries.pop() {
+                    retry
+                } else if !eof {
+                    let offset = read_offset;
+                    read_offset += u64::from(read_len);
+                    (offset, read_len)
+                } else {
+                    break;
+                };
+
+                reads.push_back((offset, len, self.send_read_request(offset, len)?));
+            }
+
+            let (offset, len, future) = match reads.pop_front() {
+                Some(read) => read,
+                None => break,
+            };
+
+            let data = match wait_for_read(self, future).await? {
+                Some(data) => data,
+                None => {
+                    eof = true;
+                    continue;
+                }
+            };
+
+            let n = data.len() as u32;
+            if n < len {
+                pending_retries.push((offset + u64::from(n), len - n));
+            }
+
+            let id = dst.inner.write_end.get_id_mut();
+            let (write_end, handle) = dst.get_inner();
+            let future = write_end
+                .send_write_request_buffered(id, handle, offset, Cow::Borrowed(&data))?
+                .wait();
+            dst.get_auxiliary().wakeup_flush_task();
+
+            writes.push_back(future);
+            copied += u64::from(n);
+
+            if writes.len() >= max_requests {
+                wait_for_write(dst, writes.pop_front().unwrap()).await?;
+            }
+        }
+
+        for future in writes {
+            wait_for_write(dst, future).await?;
+        }
+
+        Ok(copied)
+    }
+}
+
+impl File {
+    /// Write data read from `reader` until EOF into `self`, using
+    /// write requests with up to `max_requests` in flight at the same time.
+    ///
+    /// `acked` is set to the number of bytes from the offset of `self`
+    /// that are acknowledged by the server, even if it fails.
+    /// The acknowledged bytes are not necessarily durable.
+    ///
+    /// The offset of `self` is unchanged.
+    ///
+    /// Return number of bytes written.
+    pub(crate) async fn write_all_from_pipelined<R>(
+        &mut self,
+        reader: &mut R,
+        max_requests: NonZeroUsize,
+        acked: &mut u64,
+    ) -> Result<u64, Error>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        self.check_for_writable()?;
+
+        let max_requests = max_requests.get();
+        let write_len = self.max_write_len_impl() as usize;
+
+        // Writes are waited in the order they are sent, so all data before
+        // the first pending write is acknowledged.
+        let mut writes: VecDeque<(u64, AwaitableStatusFuture)> = VecDeque::new();
+        let mut buffer = BytesMut::new();
+        let mut written = 0;
+
+        *acked = 0;
+
+        loop {
+            buffer.resize(write_len, 0);
+            let n = reader.read(&mut buffer).await?;
+            if n == 0 {
+                break;
+            }
+            // The request keeps a reference to the data until it is sent.
+            let data = buffer.split_to(n).freeze();
+
+            let id = self.inner.write_end.get_id_mut();
+            let offset = self.offset + written;
+            let (write_end, handle) = self.get_inner();
+            let future = write_end
+                .send_write_request_zero_copy(id, handle, offset, &[data])?
+                .wait();
+            self.get_auxiliary().wakeup_flush_task();
+
+            written += n as u64;
+            writes.push_back((written, future));
+
+            if writes.len() >= max_requests {
+                let (end, future) = writes.pop_front().unwrap();
+                wait_for_write(self, future).await?;
+                self.report_progress(end - *acked);
+                *acked = end;
+            }
+        }
+
+        for (end, future) in writes {
+            wait_for_write(self, future).await?;
+            self.report_progress(end - *acked);
+            *acked = end;
+        }
+
+        Ok(written)
+    }
+}
+
+impl File {
+    /// Read data from `self` until EOF and write it into `writer`, using
+    /// read requests with up to `max_requests` in flight at the same time.
+    ///
+    /// After a successful function call, the offset of `self` is unchanged.
+    ///
+    /// Return number of bytes read.
+    pub(crate) async fn read_all_into_pipelined<W>(
+        &mut self,
+        writer: &mut W,
+        max_requests: NonZeroUsize,
+    ) -> Result<u64, Error>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        self.check_for_readable()?;
+
+        let max_requests = max_requests.get();
+        let read_len = self.max_read_len_impl();
+
+        let mut reads: VecDeque<(u64, u32, AwaitableDataFuture)> = VecDeque::new();
+
+        let mut read_offset = self.offset;
+        let mut eof = false;
+        let mut copied = 0;
+
+        while !eof {
+            while reads.len() < max_requests {
+                reads.push_back((
+                    read_offset,
+                    read_len,
+                    self.send_read_request(read_offset, read_len)?,
+                ));
+                read_offset += u64::from(read_len);
+            }
+
+            let (mut offset, mut len, mut future) = reads.pop_front().unwrap();
+
+            loop {
+                let data = match wait_for_read(self, future).await? {
+                    Some(data) => data,
+                    None => {
+                        eof = true;
+                        break;
+                    }
+                };
+
+                writer.write_all(&data).await?;
+                copied += data.len() as u64;
+                self.report_progress(data.len() as u64);
+
+                let n = data.len() as u32;
+                if n >= len {
+                    break;
+                }
+
+                // Data must be written in order, so the rest of the short
+                // read is read before the requests following it.
+                offset += u64::from(n);
+                len -= n;
+                future = self.send_read_request(offset, len)?;
+            }
+        }
+
+        // Requests following EOF are discarded.
+        for (_, _, future) in reads {
+            wait_for_read(self, future).await?;
+        }
+
+        writer.flush().await?;
+
+        Ok(copied)
+    }
+
+    pub(super) fn send_read_request(
+        &mut self,
+        offset: u64,
+        len: u32,
+    ) -> Result<AwaitableDataFuture, Error> {
+        let id = self.inner.write_end.get_id_mut();
+        let (write_end, handle) = self.get_inner();
+        let future = write_end
+            .send_read_request(id, handle, offset, len, None)?
+            .wait();
+        self.get_auxiliary().wakeup_flush_task();
+
+        Ok(future)
+    }
+}
+
+/// Return `None` on EOF.
+async fn wait_for_read(
+    file: &mut File,
+    future: AwaitableDataFuture,
+) -> Result<Option<Box<[u8]>>, Error> {
+    let (id, data) = file.inner.write_end.cancel_if_task_failed(future).await?;
+    file.inner.write_end.cache_id_mut(id);
+
+    match data {
+        Data::AllocatedBox(data) if !data.is_empty() => Ok(Some(data)),
+        Data::AllocatedBox(_) | Data::Eof => Ok(None),
+        Data::Buffer(_) => std::unreachable!("No buffer is provided"),
+    }
+}
+
+async fn wait_for_write(dst: &mut File, future: AwaitableStatusFuture) -> Result<(), Error> {
+    let (id, ()) = dst.inner.write_end.cancel_if_task_failed(future).await?;
+    dst.inner.write_end.cache_id_mut(id);
+
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use crate::{
+    fs::{checksum_range, Checksum, ChecksumMethod, FsStats},
     lowlevel::{self, CreateFlags, Data, Extensions, FileAttrs, Handle},
     metadata::{MetaData, MetaDataBuilder, Permissions},
-    Auxiliary, Error, Id, OwnedHandle, SftpHandle, WriteEnd, WriteEndWithCachedId,
+    Auxiliary, Error, Id, OwnedHandle, Progress, SftpHandle, WriteEnd, WriteEndWithCachedId,
 };
 
 use std::{
@@ -10,14 +11,15 @@ use std::{
     convert::TryInto,
     future::Future,
     io::{self, IoSlice},
-    num::NonZeroU64,
+    num::{NonZeroU64, NonZeroUsize},
+    ops::RangeBounds,
     path::Path,
     pin::Pin,
     task::{Context, Poll},
 };
 
 use bytes::{Buf, Bytes, BytesMut};
-use tokio::io::AsyncSeek;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite};
 use tokio_io_utility::IoSliceExt;
 
 mod tokio_compat_file;
@@ -26,6 +28,14 @@ pub use tokio_compat_file::{TokioCompatFile, DEFAULT_BUFLEN};
 mod utility;
 use utility::{take_bytes, take_io_slices};
 
+mod copy;
+
+mod stream;
+pub use stream::FileStream;
+
+mod sink;
+pub use sink::FileSink;
+
 /// Options and flags which can be used to configure how a file is opened.
 #[derive(Debug, Clone)]
 pub struct OpenOptions {
@@ -124,6 +134,11 @@ impl OpenOptions {
         self
     }
 
+    /// Opens a file at `path` with the options specified by `self`.
+    ///
+    /// If [`Sftp::max_open_handles`](crate::sftp::Sftp::max_open_handles)
+    /// is reached, it waits until another file or directory is closed.
+    ///
     /// # Cancel Safety
     ///
     /// This function is cancel safe.
@@ -163,17 +178,20 @@ impl OpenOptions {
             options.open(filename)
         };
 
+        let permit = OwnedHandle::acquire_permit(&mut write_end).await?;
+
         let handle = write_end
             .send_request(|write_end, id| Ok(write_end.send_open_file_request(id, params)?.wait()))
             .await?;
 
         Ok(File {
-            inner: OwnedHandle::new(write_end, handle),
+            inner: OwnedHandle::new(write_end, handle, permit),
 
             is_readable: options.get_read(),
             is_writable: options.get_write(),
             need_flush: false,
             offset: 0,
+            progress: None,
         })
     }
 }
@@ -184,6 +202,9 @@ impl OpenOptions {
 /// underlying file handle as the existing File instance, while reads, writes
 /// and seeks can be performed independently.
 ///
+/// Alternatively, [`File::read_at`] and [`File::write_at`] can be used to
+/// read and write concurrently at different offsets of the same [`File`].
+///
 /// If you want a file that implements [`tokio::io::AsyncRead`] and
 /// [`tokio::io::AsyncWrite`], checkout [`TokioCompatFile`].
 #[derive(Debug)]
@@ -194,6 +215,7 @@ pub struct File {
     is_writable: bool,
     need_flush: bool,
     offset: u64,
+    progress: Option<Progress>,
 }
 
 impl Clone for File {
@@ -204,6 +226,7 @@ impl Clone for File {
             is_readable: self.is_readable,
             need_flush: false,
             offset: self.offset,
+            progress: self.progress.clone(),
         }
     }
 }
@@ -248,6 +271,12 @@ impl File {
         (&mut self.inner.write_end, Cow::Borrowed(&self.inner.handle))
     }
 
+    fn report_progress(&self, n: u64) {
+        if let Some(progress) = &self.progress {
+            progress.add(n);
+        }
+    }
+
     fn check_for_writable_io_err(&self) -> Result<(), io::Error> {
         if !self.is_writable {
             Err(io::Error::new(
@@ -302,6 +331,28 @@ impl File {
         self.inner.send_request(f).await
     }
 
+    async fn send_writable_request_shared<Func, F, R>(&self, f: Func) -> Result<R, Error>
+    where
+        Func: FnOnce(&mut WriteEnd, Cow<'_, Handle>, Id) -> Result<F, Error> + Send,
+        F: Future<Output = Result<(Id, R), Error>> + Send + 'static,
+        R: Send,
+    {
+        self.check_for_writable()?;
+
+        self.inner.send_request_shared(f).await
+    }
+
+    async fn send_readable_request_shared<Func, F, R>(&self, f: Func) -> Result<R, Error>
+    where
+        Func: FnOnce(&mut WriteEnd, Cow<'_, Handle>, Id) -> Result<F, Error> + Send,
+        F: Future<Output = Result<(Id, R), Error>> + Send + 'static,
+        R: Send,
+    {
+        self.check_for_readable()?;
+
+        self.inner.send_request_shared(f).await
+    }
+
     /// Close the [`File`], send the close request
     /// if this is the last reference.
     ///
@@ -392,7 +443,94 @@ impl File {
             Ok(write_end.send_fstat_request(id, handle)?.wait())
         })
         .await
-        .map(MetaData::new)
+        .map(MetaData::from_attrs)
+    }
+
+    /// Returns statistics of the filesystem containing the underlying file.
+    ///
+    /// # Precondition
+    ///
+    /// Require extension `fstatvfs`
+    ///
+    /// You can check it with [`Sftp::support_fstatvfs`](crate::sftp::Sftp::support_fstatvfs).
+    ///
+    /// # Cancel Safety
+    ///
+    /// This function is cancel safe.
+    pub async fn statvfs(&mut self) -> Result<FsStats, Error> {
+        if !self
+            .get_auxiliary()
+            .extensions()
+            .contains(Extensions::FSTATVFS)
+        {
+            return Err(Error::UnsupportedExtension(&"fstatvfs"));
+        }
+
+        self.inner
+            .send_request(|write_end, handle, id| {
+                Ok(write_end.send_fstatvfs_request(id, handle)?.wait())
+            })
+            .await
+            .map(FsStats::new)
+    }
+
+    /// Returns hashes of the underlying file calculated by the server.
+    ///
+    /// * `algorithms` - comma-separated list of hash algorithms,
+    ///   e.g. `"sha256,md5"`, the server uses the first one it supports.
+    /// * `range` - range of the file to hash, unbounded end means
+    ///   up to the end of the file.
+    /// * `block_size` - `0` returns one hash for the whole range, otherwise
+    ///   the range is split into blocks of `block_size` bytes (at least 256)
+    ///   and one hash is returned for each block.
+    ///
+    /// The file must be opened for reading.
+    ///
+    /// # Precondition
+    ///
+    /// Require extension `check-file`, or extension `md5-hash-handle` if `algorithms`
+    /// contains `md5` and `block_size` is `0`.
+    ///
+    /// You can check the algorithms supported with
+    /// [`Sftp::checksum_algorithms`](crate::sftp::Sftp::checksum_algorithms).
+    ///
+    /// # Cancel Safety
+    ///
+    /// This function is cancel safe.
+    pub async fn checksum(
+        &mut self,
+        algorithms: &str,
+        range: impl RangeBounds<u64>,
+        block_size: u32,
+    ) -> Result<Checksum, Error> {
+        let (offset, len) = checksum_range(range)?;
+        let method = ChecksumMethod::new(
+            &self.inner.write_end,
+            algorithms,
+            block_size,
+            "md5-hash-handle",
+        )?;
+
+        match method {
+            ChecksumMethod::CheckFile => self
+                .send_readable_request(|write_end, handle, id| {
+                    Ok(write_end
+                        .send_check_file_handle_request(
+                            id, handle, algorithms, offset, len, block_size,
+                        )?
+                        .wait())
+                })
+                .await
+                .map(Checksum::new),
+            ChecksumMethod::Md5Hash => self
+                .send_readable_request(|write_end, handle, id| {
+                    Ok(write_end
+                        .send_md5_hash_handle_request(id, handle, offset, len, &[])?
+                        .wait())
+                })
+                .await
+                .map(Checksum::from_md5_hash),
+        }
     }
 
     /// * `n` - number of bytes to read in
@@ -421,6 +559,7 @@ impl File {
             Data::Eof => return Ok(None),
             _ => std::unreachable!("Expect Data::Buffer"),
         };
+        self.report_progress(buffer.len() as u64);
 
         // Adjust offset
         Pin::new(self).start_seek(io::SeekFrom::Current(n as i64))?;
@@ -456,6 +595,7 @@ impl File {
                 .wait())
         })
         .await?;
+        self.report_progress(n.into());
 
         // Adjust offset
         Pin::new(self).start_seek(io::SeekFrom::Current(n as i64))?;
@@ -492,6 +632,7 @@ impl File {
                 .wait())
         })
         .await?;
+        self.report_progress(n.into());
 
         // Adjust offset
         Pin::new(self).start_seek(io::SeekFrom::Current(n as i64))?;
@@ -526,6 +667,7 @@ impl File {
                 .wait())
         })
         .await?;
+        self.report_progress(n as u64);
 
         // Adjust offset
         Pin::new(self).start_seek(io::SeekFrom::Current(n.try_into().unwrap()))?;
@@ -642,6 +784,162 @@ impl File {
         }
     }
 
+    /// Read at most `n` bytes starting at `offset`, like `pread`.
+    ///
+    /// Unlike [`File::read`], it takes `&self` and does not change
+    /// [`File::offset`], so multiple reads and writes can be performed
+    /// concurrently on the same [`File`].
+    ///
+    /// If `offset` is at or beyond EOF or `n == 0`, then `None` is returned.
+    ///
+    /// NOTE that the returned buffer might be smaller than `n`.
+    ///
+    /// # Cancel Safety
+    ///
+    /// This function is cancel safe.
+    pub async fn read_at(
+        &self,
+        offset: u64,
+        n: u32,
+        buffer: BytesMut,
+    ) -> Result<Option<BytesMut>, Error> {
+        if n == 0 {
+            return Ok(None);
+        }
+
+        let n: u32 = min(n, self.max_read_len_impl());
+
+        let data = self
+            .send_readable_request_shared(|write_end, handle, id| {
+                Ok(write_end
+                    .send_read_request(id, handle, offset, n, Some(buffer))?
+                    .wait())
+            })
+            .await?;
+
+        let buffer = match data {
+            Data::Buffer(buffer) => buffer,
+            Data::Eof => return Ok(None),
+            _ => std::unreachable!("Expect Data::Buffer"),
+        };
+        self.report_progress(buffer.len() as u64);
+
+        Ok(Some(buffer))
+    }
+
+    /// Write data starting at `offset`, like `pwrite`.
+    ///
+    /// Unlike [`File::write`], it takes `&self` and does not change
+    /// [`File::offset`], so multiple reads and writes can be performed
+    /// concurrently on the same [`File`].
+    ///
+    /// NOTE that this API might only write part of the `buf`.
+    ///
+    /// # Cancel Safety
+    ///
+    /// This function is cancel safe.
+    pub async fn write_at(&self, offset: u64, buf: &[u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // sftp v3 cannot send more than self.max_write_len() data at once.
+        let max_write_len = self.max_write_len_impl();
+
+        let n: u32 = buf
+            .len()
+            .try_into()
+            .map(|n| min(n, max_write_len))
+            .unwrap_or(max_write_len);
+
+        let buf = &buf[..(n as usize)];
+
+        self.send_writable_request_shared(|write_end, handle, id| {
+            Ok(write_end
+                .send_write_request_buffered(id, handle, offset, Cow::Borrowed(buf))?
+                .wait())
+        })
+        .await?;
+        self.report_progress(n.into());
+
+        Ok(n as usize)
+    }
+
+    /// Write from multiple buffer at once starting at `offset`.
+    ///
+    /// Same as [`File::write_at`], except that data is taken from `bufs`.
+    ///
+    /// NOTE that this API might only write part of the `buf`.
+    pub async fn write_vectorized_at(
+        &self,
+        offset: u64,
+        bufs: &[IoSlice<'_>],
+    ) -> Result<usize, Error> {
+        if bufs.is_empty() {
+            return Ok(0);
+        }
+
+        // sftp v3 cannot send more than self.max_write_len() data at once.
+        let max_write_len = self.max_write_len_impl();
+
+        let (n, bufs, buf) = if let Some(res) = take_io_slices(bufs, max_write_len as usize) {
+            res
+        } else {
+            return Ok(0);
+        };
+
+        let n: u32 = n.try_into().unwrap();
+
+        let buffers = [bufs, &buf];
+
+        self.send_writable_request_shared(|write_end, handle, id| {
+            Ok(write_end
+                .send_write_request_buffered_vectored2(id, handle, offset, &buffers)?
+                .wait())
+        })
+        .await?;
+        self.report_progress(n.into());
+
+        Ok(n as usize)
+    }
+
+    /// Zero copy write starting at `offset`.
+    ///
+    /// Same as [`File::write_at`], except that data is taken from `bytes_slice`
+    /// without copying.
+    ///
+    /// NOTE that this API might only write part of the `buf`.
+    pub async fn write_zero_copy_at(
+        &self,
+        offset: u64,
+        bytes_slice: &[Bytes],
+    ) -> Result<usize, Error> {
+        if bytes_slice.is_empty() {
+            return Ok(0);
+        }
+
+        // sftp v3 cannot send more than self.max_write_len() data at once.
+        let max_write_len = self.max_write_len_impl();
+
+        let (n, bufs, buf) = if let Some(res) = take_bytes(bytes_slice, max_write_len as usize) {
+            res
+        } else {
+            return Ok(0);
+        };
+
+        let buffers = [bufs, &buf];
+
+        self.send_writable_request_shared(|write_end, handle, id| {
+            Ok(write_end
+                .send_write_request_zero_copy2(id, handle, offset, &buffers)?
+                .wait())
+        })
+        .await?;
+        self.report_progress(n as u64);
+
+        Ok(n)
+    }
+
     /// Return the offset of the file.
     pub fn offset(&self) -> u64 {
         self.offset
@@ -740,6 +1038,107 @@ impl File {
         self.copy_to_impl(dst, 0).await
     }
 
+    /// Write data read from `reader` until EOF into `self`.
+    ///
+    /// Unlike [`File::write_all`], up to `max_requests` write requests at
+    /// different offsets are kept in flight at the same time, so the
+    /// throughput is not limited by the round-trip time.
+    /// openssh uses [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`].
+    ///
+    /// It stops at the first error. Either way, the offset of `self` is
+    /// increased by the number of bytes that are acknowledged by the server,
+    /// so that on failure, [`File::offset`] is the highest offset up to which
+    /// all write requests are acknowledged, and the upload can be resumed from it.
+    ///
+    /// An acknowledged write is not necessarily durable, since the server
+    /// might not have flushed it to disk yet. Use [`File::sync_all`]
+    /// if the data must survive a crash of the server.
+    ///
+    /// Return number of bytes written.
+    pub async fn write_all_from<R>(
+        &mut self,
+        reader: &mut R,
+        max_requests: NonZeroUsize,
+    ) -> Result<u64, Error>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        let mut acked = 0;
+        let res = self
+            .write_all_from_pipelined(reader, max_requests, &mut acked)
+            .await;
+        self.offset += acked;
+
+        res
+    }
+
+    /// Read data from `self` until EOF and write it into `writer` in order.
+    ///
+    /// Unlike [`File::read_all`], up to `max_requests` read requests at
+    /// different offsets are kept in flight at the same time, so the
+    /// throughput is not limited by the round-trip time.
+    /// openssh uses [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`].
+    ///
+    /// Short reads are completed by reading the rest before any data
+    /// after it is written into `writer`.
+    ///
+    /// After a successful function call, the offset of `self` is increased
+    /// by the number of bytes read and `writer` is flushed.
+    ///
+    /// Return number of bytes read.
+    pub async fn read_all_into<W>(
+        &mut self,
+        writer: &mut W,
+        max_requests: NonZeroUsize,
+    ) -> Result<u64, Error>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let n = self.read_all_into_pipelined(writer, max_requests).await?;
+        self.offset += n;
+
+        Ok(n)
+    }
+
+    /// Convert the [`File`] into a [`Stream`](futures_core::Stream) of the data
+    /// read from the offset of the file until EOF.
+    ///
+    /// Up to `max_requests` read requests at different offsets are kept in
+    /// flight at the same time, and the buffers returned by the server are
+    /// yielded in order without being copied.
+    /// openssh uses [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`].
+    pub fn into_stream(self, max_requests: NonZeroUsize) -> FileStream {
+        FileStream::new(self, max_requests)
+    }
+
+    /// Convert the [`File`] into a [`Sink`](futures_sink::Sink) writing
+    /// [`Bytes`] from the offset of the file without copying them.
+    ///
+    /// Up to `max_requests` write requests are kept in flight at the same
+    /// time, and errors are reported once the failed write is waited for.
+    /// openssh uses [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`].
+    ///
+    /// The file is closed once the [`FileSink`] is dropped, so make sure to
+    /// call [`Sink::poll_close`](futures_sink::Sink::poll_close) before
+    /// dropping it to find out whether all writes succeeded.
+    pub fn into_sink(self, max_requests: NonZeroUsize) -> FileSink {
+        FileSink::new(self, max_requests)
+    }
+
+    /// Attach `progress`, which is then notified of bytes read and
+    /// written by this file, or detach it with `None`.
+    ///
+    /// It is also used by [`TokioCompatFile`] created from this file,
+    /// and is shared with clones of this file.
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    /// Return the [`Progress`] attached to this file.
+    pub fn progress(&self) -> Option<&Progress> {
+        self.progress.as_ref()
+    }
+
     /// No-op to be compatible with [`TokioCompatFile::as_mut_file`]
     pub fn as_mut_file(&mut self) -> &mut File {
         self
@@ -0,0 +1,258 @@
+use super::File;
+use crate::{cancel_error, lowlevel, Error};
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+#[cfg(feature = "buf-list")]
+use buf_list::BufList;
+use bytes::{Buf, Bytes};
+use futures_sink::Sink;
+use pin_project::{pin_project, pinned_drop};
+use tokio_util::sync::WaitForCancellationFutureOwned;
+
+type AwaitableStatusFuture = lowlevel::AwaitableStatusFuture<crate::Buffer>;
+
+/// [`Sink`] writing [`Bytes`] into a [`File`] without copying them,
+/// created by [`File::into_sink`].
+///
+/// With feature `buf-list`, it also accepts [`BufList`](buf_list::BufList),
+/// whose chunks are sent together in one write request if they fit in
+/// the maximum write length of the server.
+///
+/// Data is written sequentially starting at the offset of the file,
+/// and [`Sink::poll_ready`] waits until less than `max_requests` write
+/// requests are in flight. Since an item larger than the maximum write
+/// length of the server is split into multiple requests, sending it might
+/// exceed `max_requests` temporarily.
+///
+/// Errors returned by the server are reported by the next call to
+/// [`Sink::poll_ready`], [`Sink::poll_flush`] or [`Sink::poll_close`]
+/// that waits for the failed write.
+#[derive(Debug)]
+#[pin_project(PinnedDrop)]
+pub struct FileSink {
+    file: File,
+    max_requests: usize,
+
+    /// Offset of the next write request.
+    offset: u64,
+    /// Pending write requests with the number of bytes written.
+    writes: VecDeque<(u32, AwaitableStatusFuture)>,
+
+    /// cancellation_fut is not only cancel-safe, but also can be polled after
+    /// it is ready.
+    ///
+    /// Once it is ready, all polls after that immediately return Poll::Ready(())
+    #[pin]
+    cancellation_fut: WaitForCancellationFutureOwned,
+}
+
+impl FileSink {
+    pub(super) fn new(file: File, max_requests: NonZeroUsize) -> Self {
+        Self {
+            cancellation_fut: file.get_auxiliary().cancel_token.clone().cancelled_owned(),
+            max_requests: max_requests.get(),
+            offset: file.offset,
+            writes: VecDeque::new(),
+            file,
+        }
+    }
+
+    /// Return offset of the next write, which is the offset of the file
+    /// plus the number of bytes sent so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Wait for the oldest write request to be acknowledged.
+    fn poll_ack(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        let this = self.project();
+
+        let (len, future) = match this.writes.front_mut() {
+            Some((len, future)) => (*len, future),
+            None => return Poll::Ready(Ok(())),
+        };
+
+        let res = {
+            let cancellation_fut = this.cancellation_fut;
+            let fut = async move {
+                tokio::select! {
+                    biased;
+
+                    _ = cancellation_fut => Err(cancel_error()),
+                    res = future => res,
+                }
+            };
+
+            tokio::pin!(fut);
+
+            ready!(fut.poll(cx))
+        };
+        this.writes.pop_front();
+
+        let (id, ()) = res?;
+        this.file.inner.write_end.cache_id_mut(id);
+        this.file.report_progress(len.into());
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl FileSink {
+    fn poll_ready_impl(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.file.check_for_writable()?;
+
+        while self.writes.len() >= self.max_requests {
+            ready!(self.as_mut().poll_ack(cx))?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Send `item` in write requests of at most the maximum write length
+    /// of the server, each containing as many chunks of `item` as fit.
+    fn start_send_impl(self: Pin<&mut Self>, mut item: impl Buf) -> Result<(), Error> {
+        let this = self.project();
+        let file = this.file;
+
+        file.check_for_writable()?;
+
+        // sftp v3 cannot send more than self.max_write_len() data at once.
+        let max_write_len = file.max_write_len_impl() as usize;
+
+        let mut data = Vec::new();
+
+        while item.has_remaining() {
+            let mut len = 0;
+
+            data.clear();
+            while item.has_remaining() && len < max_write_len {
+                // Zero-copy, since it takes at most the current chunk.
+                let chunk = item.copy_to_bytes(item.chunk().len().min(max_write_len - len));
+                len += chunk.len();
+                data.push(chunk);
+            }
+            let len = len as u32;
+
+            let id = file.inner.write_end.get_id_mut();
+            let (write_end, handle) = file.get_inner();
+            let future = write_end
+                .send_write_request_zero_copy(id, handle, *this.offset, &data)?
+                .wait();
+            file.get_auxiliary().wakeup_flush_task();
+
+            this.writes.push_back((len, future));
+            *this.offset += u64::from(len);
+        }
+
+        Ok(())
+    }
+
+    fn poll_flush_impl(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        if self.writes.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Send the write requests now instead of waiting for the flush interval.
+        let auxiliary = self.file.get_auxiliary();
+        if auxiliary.get_pending_requests() != 0 {
+            auxiliary.trigger_flushing();
+        }
+
+        while !self.writes.is_empty() {
+            ready!(self.as_mut().poll_ack(cx))?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Sink<Bytes> for FileSink {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.poll_ready_impl(cx)
+    }
+
+    /// Send `item`, split into multiple write requests if it is larger
+    /// than the maximum write length of the server.
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Error> {
+        self.start_send_impl(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.poll_flush_impl(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.poll_flush_impl(cx)
+    }
+}
+
+#[cfg(feature = "buf-list")]
+impl Sink<BufList> for FileSink {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.poll_ready_impl(cx)
+    }
+
+    /// Send chunks of `item` together in write requests of at most
+    /// the maximum write length of the server.
+    fn start_send(self: Pin<&mut Self>, item: BufList) -> Result<(), Error> {
+        self.start_send_impl(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.poll_flush_impl(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.poll_flush_impl(cx)
+    }
+}
+
+impl FileSink {
+    async fn do_drop(mut file: File, writes: VecDeque<(u32, AwaitableStatusFuture)>) {
+        for (_, future) in writes {
+            if let Ok((id, _)) = future.await {
+                file.inner.write_end.cache_id_mut(id);
+            }
+        }
+    }
+}
+
+/// We need to keep polling the futures stored internally, otherwise it would
+/// drop the internal request ids too early, causing read task to fail
+/// when they should not fail.
+#[pinned_drop]
+impl PinnedDrop for FileSink {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+
+        if this.writes.is_empty() {
+            return;
+        }
+
+        let file = this.file.clone();
+        let writes = std::mem::take(this.writes);
+
+        let cancellation_fut = file.get_auxiliary().cancel_token.clone().cancelled_owned();
+        let do_drop_fut = Self::do_drop(file, writes);
+
+        this.file.get_auxiliary().tokio_handle().spawn(async move {
+            tokio::select! {
+                biased;
+
+                _ = cancellation_fut => (),
+                _ = do_drop_fut => (),
+            }
+        });
+    }
+}
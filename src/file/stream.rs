@@ -0,0 +1,189 @@
+use super::File;
+use crate::{
+    cancel_error,
+    lowlevel::{self, Data},
+    Error,
+};
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_core::stream::{FusedStream, Stream};
+use pin_project::{pin_project, pinned_drop};
+use tokio_util::sync::WaitForCancellationFutureOwned;
+
+type AwaitableDataFuture = lowlevel::AwaitableDataFuture<crate::Buffer>;
+
+/// Stream of data read from a [`File`], created by [`File::into_stream`].
+///
+/// It keeps multiple read requests in flight and yields the buffers
+/// returned by the server in order, until EOF is reached.
+#[derive(Debug)]
+#[pin_project(PinnedDrop)]
+pub struct FileStream {
+    file: File,
+    max_requests: usize,
+    read_len: u32,
+
+    /// Offset of the next read request.
+    read_offset: u64,
+    /// Pending read requests in the order of offset,
+    /// with the offset and length requested.
+    reads: VecDeque<(u64, u32, AwaitableDataFuture)>,
+    /// Set once EOF is reached or an error occurs.
+    terminated: bool,
+
+    /// cancellation_fut is not only cancel-safe, but also can be polled after
+    /// it is ready.
+    ///
+    /// Once it is ready, all polls after that immediately return Poll::Ready(())
+    #[pin]
+    cancellation_fut: WaitForCancellationFutureOwned,
+}
+
+impl FileStream {
+    pub(super) fn new(file: File, max_requests: NonZeroUsize) -> Self {
+        Self {
+            cancellation_fut: file.get_auxiliary().cancel_token.clone().cancelled_owned(),
+            max_requests: max_requests.get(),
+            read_len: file.max_read_len_impl(),
+            read_offset: file.offset,
+            reads: VecDeque::new(),
+            terminated: false,
+            file,
+        }
+    }
+
+    fn poll_next_bytes(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        let this = self.project();
+        let file = this.file;
+        let reads = this.reads;
+
+        file.check_for_readable()?;
+
+        while reads.len() < *this.max_requests {
+            let offset = *this.read_offset;
+            let len = *this.read_len;
+
+            reads.push_back((offset, len, file.send_read_request(offset, len)?));
+            *this.read_offset += u64::from(len);
+        }
+
+        let (offset, len, future) = reads.front_mut().expect("max_requests is non-zero");
+        let (offset, len) = (*offset, *len);
+
+        let res = {
+            let cancellation_fut = this.cancellation_fut;
+            let fut = async move {
+                tokio::select! {
+                    biased;
+
+                    _ = cancellation_fut => Err(cancel_error()),
+                    res = future => res,
+                }
+            };
+
+            tokio::pin!(fut);
+
+            ready!(fut.poll(cx))
+        };
+        reads.pop_front();
+
+        let (id, data) = res?;
+        file.inner.write_end.cache_id_mut(id);
+
+        let data = match data {
+            Data::AllocatedBox(data) if !data.is_empty() => data,
+            Data::AllocatedBox(_) | Data::Eof => {
+                *this.terminated = true;
+                return Poll::Ready(None);
+            }
+            Data::Buffer(_) => std::unreachable!("No buffer is provided"),
+        };
+
+        let n = data.len() as u32;
+        if n < len {
+            // Data must be yielded in order, so the rest of the short
+            // read is read before the requests following it.
+            let offset = offset + u64::from(n);
+            let len = len - n;
+
+            reads.push_front((offset, len, file.send_read_request(offset, len)?));
+        }
+
+        file.report_progress(n.into());
+
+        Poll::Ready(Some(Ok(Bytes::from(data))))
+    }
+}
+
+impl Stream for FileStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.terminated {
+            return Poll::Ready(None);
+        }
+
+        let res = ready!(self.as_mut().poll_next_bytes(cx));
+        if let Some(Err(_)) = &res {
+            *self.project().terminated = true;
+        }
+
+        Poll::Ready(res)
+    }
+}
+
+impl FusedStream for FileStream {
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl FileStream {
+    async fn do_drop(mut file: File, reads: VecDeque<(u64, u32, AwaitableDataFuture)>) {
+        for (_, _, future) in reads {
+            if let Ok((id, _)) = future.await {
+                file.inner.write_end.cache_id_mut(id);
+            }
+        }
+    }
+}
+
+/// We need to keep polling the futures stored internally, otherwise it would
+/// drop the internal request ids too early, causing read task to fail
+/// when they should not fail.
+#[pinned_drop]
+impl PinnedDrop for FileStream {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+
+        if this.reads.is_empty() {
+            return;
+        }
+
+        let file = this.file.clone();
+        let reads = std::mem::take(this.reads);
+
+        let cancellation_fut = file.get_auxiliary().cancel_token.clone().cancelled_owned();
+        let do_drop_fut = Self::do_drop(file, reads);
+
+        this.file.get_auxiliary().tokio_handle().spawn(async move {
+            tokio::select! {
+                biased;
+
+                _ = cancellation_fut => (),
+                _ = do_drop_fut => (),
+            }
+        });
+    }
+}
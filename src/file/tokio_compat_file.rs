@@ -263,6 +263,7 @@ impl TokioCompatFile {
                 // sftp v3 can at most read in max_read_len bytes.
                 debug_assert!(buffer.len() <= max_read_len as usize);
 
+                this.inner.report_progress(buffer.len() as u64);
                 this.buffer.unsplit(buffer);
             }
             Data::Eof => return Poll::Ready(Ok(())),
@@ -339,10 +340,10 @@ impl TokioCompatFile {
 
         let this = self.project();
 
-        let res = if let Some(element) = this.write_futures.front_mut() {
+        let (res, write_len) = if let Some(element) = this.write_futures.front_mut() {
             let res = ready!(Pin::new(&mut element.future).poll(cx));
             *this.write_len -= element.write_len;
-            res
+            (res, element.write_len)
         } else {
             // All futures consumed without error
             debug_assert_eq!(*this.write_len, 0);
@@ -357,6 +358,7 @@ impl TokioCompatFile {
         this.inner
             .inner
             .cache_id_mut(res.map_err(sftp_to_io_error)?.0);
+        this.inner.report_progress(write_len as u64);
 
         Poll::Ready(Ok(()))
     }
@@ -597,10 +599,10 @@ impl AsyncWrite for TokioCompatFile {
         let this = self.project();
 
         loop {
-            let res = if let Some(element) = this.write_futures.front_mut() {
+            let (res, write_len) = if let Some(element) = this.write_futures.front_mut() {
                 let res = ready!(Pin::new(&mut element.future).poll(cx));
                 *this.write_len -= element.write_len;
-                res
+                (res, element.write_len)
             } else {
                 // All futures consumed without error
                 debug_assert_eq!(*this.write_len, 0);
@@ -615,6 +617,7 @@ impl AsyncWrite for TokioCompatFile {
             this.inner
                 .inner
                 .cache_id_mut(res.map_err(sftp_to_io_error)?.0);
+            this.inner.report_progress(write_len as u64);
         }
     }
 
@@ -725,7 +728,10 @@ impl TokioCompatFile {
             // they assume the data has already been written and flushed, it
             // fails and we need to notify our users of the error.
             match write_element.future.await {
-                Ok((id, _)) => file.inner.cache_id_mut(id),
+                Ok((id, _)) => {
+                    file.inner.cache_id_mut(id);
+                    file.report_progress(write_element.write_len as u64);
+                }
                 Err(_err) => {
                     #[cfg(feature = "tracing")]
                     tracing::error!(?_err, "failed to write to File")
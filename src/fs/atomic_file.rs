@@ -0,0 +1,231 @@
+use crate::{
+    file::{File, OpenOptions},
+    lowlevel::{self, Extensions, SftpErrorKind},
+    metadata::Permissions,
+    Error,
+};
+
+use super::Fs;
+
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+/// Number of temporary names tried before giving up.
+const MAX_ATTEMPTS: usize = 8;
+
+/// Counter to make temporary names unique within the process.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A file that atomically replaces its target once committed, used by
+/// [`Fs::write_atomic`] and created by [`Fs::create_atomic`] to write
+/// the content incrementally.
+///
+/// Data is written to a temporary file with a unique name in the same
+/// directory as the target, which is renamed over the target using the
+/// posix-rename extension by [`AtomicFile::commit`], so readers either see
+/// the old or the new content of the target.
+///
+/// If the [`AtomicFile`] is dropped without being committed, or committing
+/// fails, the temporary file is removed.
+#[derive(Debug)]
+pub struct AtomicFile {
+    /// Only `None` once the file is closed by [`AtomicFile::commit`].
+    file: Option<File>,
+    fs: Fs,
+    path: PathBuf,
+    temp_path: PathBuf,
+    permissions: Option<Permissions>,
+    /// Set once the temporary file is renamed or removed.
+    done: bool,
+}
+
+impl AtomicFile {
+    pub(super) async fn create(fs: &Fs, path: &Path) -> Result<Self, Error> {
+        if !fs
+            .get_auxiliary()
+            .extensions()
+            .contains(Extensions::POSIX_RENAME)
+        {
+            return Err(Error::UnsupportedExtension(&"posix-rename"));
+        }
+
+        let path = fs.concat_path_if_needed(path).into_owned();
+
+        // Paths are already joined with the cwd.
+        let mut fs = fs.clone();
+        fs.set_cwd("");
+
+        // Keep permissions of the file being replaced.
+        let permissions = match fs.metadata(&path).await {
+            Ok(metadata) => metadata.permissions(),
+            Err(Error::SftpError(SftpErrorKind::NoSuchFile, _)) => None,
+            Err(err) => return Err(err),
+        };
+
+        let mut attempts = 0;
+
+        loop {
+            let temp_path = temp_path(&path);
+
+            let res = OpenOptions::open_inner(
+                lowlevel::OpenOptions::new().write(true),
+                false,
+                false,
+                true,
+                &temp_path,
+                fs.write_end.clone(),
+            )
+            .await;
+
+            match res {
+                Ok(file) => {
+                    break Ok(Self {
+                        file: Some(file),
+                        fs,
+                        path,
+                        temp_path,
+                        permissions,
+                        done: false,
+                    })
+                }
+                // The name is most likely taken, try another one.
+                Err(Error::SftpError(SftpErrorKind::Failure, _)) if attempts + 1 < MAX_ATTEMPTS => {
+                    attempts += 1
+                }
+                Err(err) => break Err(err),
+            }
+        }
+    }
+
+    /// Return path of the target.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return path of the temporary file.
+    pub fn temp_path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    /// Set permissions of the target once committed.
+    ///
+    /// Defaults to permissions of the target if it exists when the
+    /// [`AtomicFile`] is created, otherwise the default permissions of
+    /// the server are used.
+    pub fn permissions(&mut self, perm: Permissions) -> &mut Self {
+        self.permissions = Some(perm);
+        self
+    }
+
+    /// Return the temporary file to write to.
+    pub fn as_mut_file(&mut self) -> &mut File {
+        self.file.as_mut().expect("Only taken by commit")
+    }
+
+    /// Flush the temporary file to disk if the fsync extension is supported,
+    /// apply the permissions, then rename it over the target.
+    ///
+    /// If it fails, the temporary file is removed and the target
+    /// is unchanged.
+    pub async fn commit(mut self) -> Result<(), Error> {
+        let mut file = self.file.take().expect("Only taken by commit");
+
+        match file.sync_all().await {
+            Ok(()) | Err(Error::UnsupportedExtension(_)) => (),
+            Err(err) => return Err(err),
+        }
+
+        if let Some(perm) = self.permissions {
+            file.set_permissions(perm).await?;
+        }
+
+        file.close().await?;
+
+        self.fs.rename(&self.temp_path, &self.path).await?;
+        self.done = true;
+
+        Ok(())
+    }
+
+    /// Remove the temporary file without changing the target.
+    ///
+    /// Unlike dropping the [`AtomicFile`], this waits for the temporary file
+    /// to be removed.
+    pub async fn discard(mut self) -> Result<(), Error> {
+        drop(self.file.take());
+
+        self.fs.remove_file(&self.temp_path).await?;
+        self.done = true;
+
+        Ok(())
+    }
+}
+
+impl Deref for AtomicFile {
+    type Target = File;
+
+    fn deref(&self) -> &Self::Target {
+        self.file.as_ref().expect("Only taken by commit")
+    }
+}
+
+impl DerefMut for AtomicFile {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_file()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        let write_end = &mut self.fs.write_end;
+        let id = write_end.get_id_mut();
+
+        match write_end.send_remove_request(id, Cow::Borrowed(&self.temp_path)) {
+            Ok(response) => {
+                self.fs.get_auxiliary().wakeup_flush_task();
+
+                let future = response.wait();
+                self.fs.get_auxiliary().tokio_handle().spawn(async move {
+                    let _res = future.await;
+                    #[cfg(feature = "tracing")]
+                    match _res {
+                        Ok(_) => tracing::debug!("remove temporary file success"),
+                        Err(err) => tracing::error!(?err, "failed to remove temporary file"),
+                    }
+                });
+            }
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!(?_err, "failed to send remove request");
+            }
+        }
+    }
+}
+
+/// Return a unique temporary path in the same directory as `path`.
+fn temp_path(path: &Path) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    let mut name = OsString::from(".");
+    if let Some(file_name) = path.file_name() {
+        name.push(file_name);
+    }
+    name.push(format!(".{}.{}.{:x}.tmp", process::id(), count, nanos));
+
+    path.with_file_name(name)
+}
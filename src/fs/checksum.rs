@@ -0,0 +1,126 @@
+use crate::{lowlevel, Error, SharedData};
+
+use std::{
+    io,
+    ops::{Bound, RangeBounds},
+};
+
+/// Name of the extension advertised by servers supporting
+/// `check-file-handle` and `check-file-name`.
+const CHECK_FILE: &str = "check-file";
+
+/// Hashes of a remote file calculated by the server, returned by
+/// [`Fs::checksum`](super::Fs::checksum) and
+/// [`File::checksum`](crate::file::File::checksum).
+#[repr(transparent)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Checksum(lowlevel::FileChecksum);
+
+impl Checksum {
+    pub(crate) fn new(checksum: lowlevel::FileChecksum) -> Self {
+        Self(checksum)
+    }
+
+    /// Create from reply of the md5-hash extension.
+    pub(crate) fn from_md5_hash(hash: Box<[u8]>) -> Self {
+        Self(lowlevel::FileChecksum {
+            algorithm: "md5".into(),
+            hashes: hash,
+        })
+    }
+
+    /// Returns the hash algorithm picked by the server.
+    pub fn algorithm(&self) -> &str {
+        &self.0.algorithm
+    }
+
+    /// Returns the hashes of all blocks concatenated together.
+    pub fn hashes(&self) -> &[u8] {
+        &self.0.hashes
+    }
+
+    /// Returns length of one hash.
+    ///
+    /// Return `None` if the algorithm is unknown.
+    pub fn hash_len(&self) -> Option<usize> {
+        self.0.hash_len()
+    }
+
+    /// Returns the hash of each block, in the order of the blocks.
+    ///
+    /// Return `None` if the algorithm is unknown or
+    /// the server returned malformed hashes.
+    pub fn blocks(&self) -> Option<impl Iterator<Item = &[u8]> + '_> {
+        self.0.blocks()
+    }
+}
+
+/// The extended request used to calculate the checksum.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum ChecksumMethod {
+    CheckFile,
+    Md5Hash,
+}
+
+impl ChecksumMethod {
+    /// * `md5_hash` - name of the md5-hash extension to fall back to.
+    pub(crate) fn new(
+        shared_data: &SharedData,
+        algorithms: &str,
+        block_size: u32,
+        md5_hash: &str,
+    ) -> Result<Self, Error> {
+        if shared_data.server_extension_data(CHECK_FILE).is_some() {
+            Ok(ChecksumMethod::CheckFile)
+        } else if shared_data.server_extension_data(md5_hash).is_some()
+            && block_size == 0
+            && algorithms.split(',').any(|algorithm| algorithm == "md5")
+        {
+            Ok(ChecksumMethod::Md5Hash)
+        } else {
+            Err(Error::UnsupportedExtension(&CHECK_FILE))
+        }
+    }
+}
+
+/// Return hash algorithms supported by the server.
+pub(crate) fn checksum_algorithms(shared_data: &SharedData) -> Vec<&str> {
+    let mut algorithms: Vec<&str> = shared_data
+        .server_extension_data(CHECK_FILE)
+        .and_then(|data| std::str::from_utf8(data).ok())
+        .map(|data| data.split(',').filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+
+    let support_md5_hash = shared_data.server_extension_data("md5-hash").is_some()
+        || shared_data
+            .server_extension_data("md5-hash-handle")
+            .is_some();
+
+    if support_md5_hash && !algorithms.contains(&"md5") {
+        algorithms.push("md5");
+    }
+
+    algorithms
+}
+
+/// Convert `range` to (offset, length), where length `0` means
+/// up to the end of the file.
+pub(crate) fn checksum_range(range: impl RangeBounds<u64>) -> Result<(u64, u64), Error> {
+    let offset = match range.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(end) => end.saturating_add(1),
+        Bound::Excluded(end) => *end,
+        Bound::Unbounded => return Ok((offset, 0)),
+    };
+
+    if end <= offset {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "Empty range for checksum").into())
+    } else {
+        Ok((offset, end - offset))
+    }
+}
@@ -42,7 +42,7 @@ impl DirEntry {
 
     /// Return metadata for the dir entry.
     pub fn metadata(&self) -> MetaData {
-        MetaData::new(self.0.attrs)
+        MetaData::from_attrs(self.0.attrs.clone())
     }
 
     /// Return the file type for the dir entry.
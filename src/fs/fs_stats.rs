@@ -0,0 +1,93 @@
+use crate::lowlevel;
+
+/// Statistics of a remote filesystem, returned by
+/// [`Fs::statvfs`](super::Fs::statvfs) and
+/// [`File::statvfs`](crate::file::File::statvfs).
+///
+/// This is a specialized version of `struct statvfs` in POSIX.
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FsStats(lowlevel::FsStats);
+
+impl FsStats {
+    pub(crate) fn new(stats: lowlevel::FsStats) -> Self {
+        Self(stats)
+    }
+
+    /// Returns the filesystem block size.
+    pub fn block_size(&self) -> u64 {
+        self.0.block_size
+    }
+
+    /// Returns the fundamental filesystem block size, which is the
+    /// unit of [`FsStats::blocks`], [`FsStats::blocks_free`] and
+    /// [`FsStats::blocks_available`].
+    pub fn fragment_size(&self) -> u64 {
+        self.0.fragment_size
+    }
+
+    /// Returns the total number of blocks.
+    pub fn blocks(&self) -> u64 {
+        self.0.blocks
+    }
+
+    /// Returns the number of free blocks.
+    pub fn blocks_free(&self) -> u64 {
+        self.0.blocks_free
+    }
+
+    /// Returns the number of free blocks available to unprivileged users.
+    pub fn blocks_available(&self) -> u64 {
+        self.0.blocks_avail
+    }
+
+    /// Returns the total number of inodes.
+    pub fn files(&self) -> u64 {
+        self.0.files
+    }
+
+    /// Returns the number of free inodes.
+    pub fn files_free(&self) -> u64 {
+        self.0.files_free
+    }
+
+    /// Returns the number of free inodes available to unprivileged users.
+    pub fn files_available(&self) -> u64 {
+        self.0.files_avail
+    }
+
+    /// Returns the filesystem ID.
+    pub fn fsid(&self) -> u64 {
+        self.0.fsid
+    }
+
+    /// Returns the maximum length of filenames.
+    pub fn max_filename_len(&self) -> u64 {
+        self.0.name_max
+    }
+
+    /// Tests whether the filesystem is mounted read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.0.flags & lowlevel::FsStats::RDONLY != 0
+    }
+
+    /// Tests whether the filesystem ignores setuid/setgid bits.
+    pub fn is_nosuid(&self) -> bool {
+        self.0.flags & lowlevel::FsStats::NOSUID != 0
+    }
+
+    /// Returns the total size of the filesystem in bytes.
+    pub fn total_space(&self) -> u64 {
+        self.0.blocks.saturating_mul(self.0.fragment_size)
+    }
+
+    /// Returns the number of free bytes.
+    pub fn free_space(&self) -> u64 {
+        self.0.blocks_free.saturating_mul(self.0.fragment_size)
+    }
+
+    /// Returns the number of free bytes available to unprivileged users.
+    pub fn available_space(&self) -> u64 {
+        self.0.blocks_avail.saturating_mul(self.0.fragment_size)
+    }
+}
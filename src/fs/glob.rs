@@ -0,0 +1,568 @@
+use crate::{
+    lowlevel::{self, Extensions},
+    metadata::MetaData,
+    Error,
+};
+
+use super::{DirEntry, Fs};
+
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fmt,
+    future::{poll_fn, Future},
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::stream::{FusedStream, Stream};
+
+#[cfg(unix)]
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+type Item = Result<(PathBuf, MetaData), Error>;
+type GlobFuture = Pin<Box<dyn Future<Output = (Glob, Option<Item>)> + Send>>;
+
+/// A component of the pattern, separated by `/`.
+#[derive(Debug)]
+enum Component {
+    /// Component without any wildcard, with escapes removed.
+    Literal(OsString),
+    /// Component with wildcards.
+    Pattern(Vec<u8>),
+    /// `**`, matches zero or more directories.
+    Recursive,
+}
+
+impl Component {
+    fn new(component: &[u8]) -> Self {
+        if component == b"**" {
+            Component::Recursive
+        } else if has_wildcard(component) {
+            Component::Pattern(component.to_vec())
+        } else {
+            Component::Literal(os_string_from_bytes(unescape(component)))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Candidate {
+    path: PathBuf,
+    /// Index of the next component to match.
+    index: usize,
+    /// `None` if the path has not been read from its parent directory.
+    metadata: Option<MetaData>,
+    /// Number of directories matched by the current `**`.
+    depth: usize,
+}
+
+#[derive(Debug)]
+struct Glob {
+    fs: Fs,
+    /// Leading `~` or `~user` component to be expanded.
+    tilde: Option<OsString>,
+    components: Vec<Component>,
+    /// Candidates to visit, the last one is visited first.
+    stack: Vec<Candidate>,
+}
+
+impl Glob {
+    async fn next(mut self) -> (Self, Option<Item>) {
+        let item = self.next_inner().await;
+        (self, item)
+    }
+
+    async fn next_inner(&mut self) -> Option<Item> {
+        if let Some(tilde) = self.tilde.take() {
+            if let Err(err) = self.expand_tilde(tilde).await {
+                self.stack.clear();
+                return Some(Err(err));
+            }
+        }
+
+        while let Some(candidate) = self.stack.pop() {
+            match self.visit(candidate).await {
+                Ok(Some(item)) => return Some(Ok(item)),
+                Ok(None) => (),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        None
+    }
+
+    async fn expand_tilde(&mut self, tilde: OsString) -> Result<(), Error> {
+        if !self
+            .fs
+            .get_auxiliary()
+            .extensions()
+            .contains(Extensions::EXPAND_PATH)
+        {
+            return Err(Error::UnsupportedExtension(&"expand-path"));
+        }
+
+        let path = self.fs.canonicalize(tilde).await?;
+        self.stack[0].path = path;
+
+        Ok(())
+    }
+
+    /// Return the candidate if it matches the whole pattern.
+    async fn visit(&mut self, candidate: Candidate) -> Result<Option<(PathBuf, MetaData)>, Error> {
+        let Candidate {
+            path,
+            index,
+            metadata,
+            depth,
+        } = candidate;
+
+        let is_last = index + 1 == self.components.len();
+
+        let component = match self.components.get(index) {
+            Some(component) => component,
+            None if path.as_os_str().is_empty() => return Ok(None),
+            None => {
+                let metadata = match metadata {
+                    Some(metadata) => metadata,
+                    // Only yield paths that exist.
+                    None => match self.fs.symlink_metadata(&path).await {
+                        Ok(metadata) => metadata,
+                        Err(Error::SftpError(..)) => return Ok(None),
+                        Err(err) => return Err(err),
+                    },
+                };
+                return Ok(Some((path, metadata)));
+            }
+        };
+
+        match component {
+            Component::Literal(name) => {
+                let path = path.join(name);
+                self.stack.push(Candidate {
+                    path,
+                    index: index + 1,
+                    metadata: None,
+                    depth: 0,
+                });
+            }
+            Component::Pattern(pattern) => {
+                let pattern = pattern.clone();
+                let mut candidates = Vec::new();
+
+                for entry in self.read_dir(&path).await? {
+                    let name = os_str_to_bytes(entry.filename().as_os_str());
+                    if !matches_name(&pattern, &name) {
+                        continue;
+                    }
+
+                    let metadata = entry.metadata();
+                    if !is_last && !may_be_dir(&metadata) {
+                        continue;
+                    }
+
+                    candidates.push(Candidate {
+                        path: path.join(entry.filename()),
+                        index: index + 1,
+                        metadata: Some(metadata),
+                        depth: 0,
+                    });
+                }
+
+                self.stack.extend(candidates.into_iter().rev());
+            }
+            Component::Recursive => {
+                let mut candidates = Vec::new();
+
+                // `**` matching zero directories.
+                candidates.push(Candidate {
+                    path: path.clone(),
+                    index: index + 1,
+                    metadata,
+                    depth: 0,
+                });
+
+                if depth < lowlevel::OPENSSH_PORTABLE_MAX_DIR_DEPTH {
+                    for entry in self.read_dir(&path).await? {
+                        if os_str_to_bytes(entry.filename().as_os_str()).starts_with(b".") {
+                            continue;
+                        }
+
+                        let metadata = entry.metadata();
+                        let is_dir = metadata.file_type().map(|t| t.is_dir()).unwrap_or(false);
+
+                        // Symlinks are not followed to avoid loops.
+                        let (index, depth) = if is_dir {
+                            (index, depth + 1)
+                        } else if is_last {
+                            (index + 1, 0)
+                        } else {
+                            continue;
+                        };
+
+                        candidates.push(Candidate {
+                            path: path.join(entry.filename()),
+                            index,
+                            metadata: Some(metadata),
+                            depth,
+                        });
+                    }
+                }
+
+                self.stack.extend(candidates.into_iter().rev());
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Read entries of directory `path`, except for `.` and `..`.
+    ///
+    /// Return an empty list if the directory cannot be read.
+    async fn read_dir(&mut self, path: &Path) -> Result<Vec<DirEntry>, Error> {
+        let dir_path = if path.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            path
+        };
+
+        let read_dir = match self.fs.open_dir(dir_path).await {
+            Ok(dir) => dir.read_dir(),
+            Err(Error::SftpError(..)) => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        tokio::pin!(read_dir);
+
+        let mut entries = Vec::new();
+
+        while let Some(entry) = poll_fn(|cx| read_dir.as_mut().poll_next(cx)).await {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(Error::SftpError(..)) => break,
+                Err(err) => return Err(err),
+            };
+
+            let filename = entry.filename();
+            if filename != Path::new(".") && filename != Path::new("..") {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Stream of paths matching a pattern, along with their metadata,
+/// created by [`Fs::glob`].
+pub struct GlobStream {
+    glob: Option<Glob>,
+    future: Option<GlobFuture>,
+}
+
+impl fmt::Debug for GlobStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlobStream")
+            .field("glob", &self.glob)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GlobStream {
+    pub(super) fn new(mut fs: Fs, pattern: &Path) -> Self {
+        let pattern = os_str_to_bytes(pattern.as_os_str());
+        let mut pattern: &[u8] = &pattern;
+        let mut tilde = None;
+
+        let base = if pattern.starts_with(b"~") {
+            let end = pattern
+                .iter()
+                .position(|&c| c == b'/')
+                .unwrap_or(pattern.len());
+            tilde = Some(os_string_from_bytes(pattern[..end].to_vec()));
+            pattern = &pattern[end..];
+
+            // Replaced once the tilde is expanded.
+            PathBuf::new()
+        } else if pattern.starts_with(b"/") {
+            PathBuf::from("/")
+        } else {
+            fs.cwd().to_owned()
+        };
+
+        let components = pattern
+            .split(|&c| c == b'/')
+            .filter(|component| !component.is_empty() && *component != b".")
+            .map(Component::new)
+            .collect();
+
+        // Paths of candidates are already joined with the cwd.
+        fs.set_cwd("");
+
+        Self {
+            glob: Some(Glob {
+                fs,
+                tilde,
+                components,
+                stack: vec![Candidate {
+                    path: base,
+                    index: 0,
+                    metadata: None,
+                    depth: 0,
+                }],
+            }),
+            future: None,
+        }
+    }
+}
+
+impl Stream for GlobStream {
+    type Item = Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        let future = match &mut this.future {
+            Some(future) => future,
+            None => match this.glob.take() {
+                Some(glob) => this.future.insert(Box::pin(glob.next())),
+                None => return Poll::Ready(None),
+            },
+        };
+
+        let (glob, item) = ready!(future.as_mut().poll(cx));
+        this.future = None;
+
+        if item.is_some() {
+            this.glob = Some(glob);
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+impl FusedStream for GlobStream {
+    fn is_terminated(&self) -> bool {
+        self.glob.is_none() && self.future.is_none()
+    }
+}
+
+fn may_be_dir(metadata: &MetaData) -> bool {
+    metadata
+        .file_type()
+        .map(|t| t.is_dir() || t.is_symlink())
+        .unwrap_or(true)
+}
+
+fn has_wildcard(component: &[u8]) -> bool {
+    let mut iter = component.iter();
+
+    while let Some(c) = iter.next() {
+        match c {
+            b'*' | b'?' | b'[' => return true,
+            b'\\' => {
+                iter.next();
+            }
+            _ => (),
+        }
+    }
+
+    false
+}
+
+fn unescape(component: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(component.len());
+    let mut iter = component.iter();
+
+    while let Some(&c) = iter.next() {
+        match c {
+            b'\\' => unescaped.extend(iter.next()),
+            c => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+/// Like [`matches`], except that hidden files are only matched by
+/// patterns starting with `.`.
+fn matches_name(pattern: &[u8], name: &[u8]) -> bool {
+    if name.starts_with(b".") && !pattern.starts_with(b".") {
+        false
+    } else {
+        matches(pattern, name)
+    }
+}
+
+/// Return `true` if `name` matches `pattern`, which can contain `*`, `?`,
+/// `[...]` and characters escaped by `\`.
+fn matches(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the position in `name` it is
+    // matched up to, used to backtrack.
+    let mut star = None;
+
+    while n < name.len() {
+        let matched = match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(1),
+            Some(b'[') => match match_class(&pattern[p..], name[n]) {
+                Some((true, len)) => Some(len),
+                Some((false, _)) => None,
+                // Unterminated `[` matches itself.
+                None => (name[n] == b'[').then_some(1),
+            },
+            Some(b'\\') if p + 1 < pattern.len() => (pattern[p + 1] == name[n]).then_some(2),
+            Some(&c) => (c == name[n]).then_some(1),
+            None => None,
+        };
+
+        match (matched, star) {
+            (Some(len), _) => {
+                p += len;
+                n += 1;
+            }
+            (None, Some((star_p, star_n))) => {
+                p = star_p + 1;
+                n = star_n + 1;
+                star = Some((star_p, n));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    pattern[p.min(pattern.len())..].iter().all(|&c| c == b'*')
+}
+
+/// Match `c` against the character class at the start of `pattern`.
+///
+/// Return whether `c` matches and the length of the class,
+/// or `None` if the class is not terminated.
+fn match_class(pattern: &[u8], c: u8) -> Option<(bool, usize)> {
+    let mut i = 1;
+
+    let negate = matches!(pattern.get(i), Some(b'!') | Some(b'^'));
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+
+    loop {
+        let mut start = *pattern.get(i)?;
+        if start == b']' && !first {
+            break;
+        }
+        first = false;
+
+        if start == b'\\' {
+            i += 1;
+            start = *pattern.get(i)?;
+        }
+        i += 1;
+
+        let end = match (pattern.get(i), pattern.get(i + 1)) {
+            (Some(b'-'), Some(&end)) if end != b']' => {
+                i += 2;
+                if end == b'\\' {
+                    i += 1;
+                    *pattern.get(i - 1)?
+                } else {
+                    end
+                }
+            }
+            _ => start,
+        };
+
+        if start <= c && c <= end {
+            matched = true;
+        }
+    }
+
+    Some((matched != negate, i + 1))
+}
+
+/// Return bytes of `s`.
+#[cfg(unix)]
+fn os_str_to_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    Cow::Borrowed(s.as_bytes())
+}
+
+/// Return bytes of `s`.
+///
+/// `s` is only guaranteed to be valid bytes on unix, elsewhere
+/// it is converted to UTF-8 first, replacing invalid sequences.
+#[cfg(not(unix))]
+fn os_str_to_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    match s.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+/// Convert bytes returned by [`os_str_to_bytes`] back to [`OsString`].
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: Vec<u8>) -> OsString {
+    OsString::from_vec(bytes)
+}
+
+/// Convert bytes returned by [`os_str_to_bytes`] back to [`OsString`].
+///
+/// The bytes are always valid UTF-8 on non-unix, since patterns are only
+/// split at ASCII characters.
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches(b"*", b"abc"));
+        assert!(matches(b"*", b""));
+        assert!(matches(b"a*c", b"abbbc"));
+        assert!(matches(b"a*b*c", b"aXbYbZc"));
+        assert!(!matches(b"a*c", b"abcd"));
+        assert!(matches(b"app-*.log", b"app-1.log"));
+        assert!(!matches(b"app-*.log", b"app-1.log.gz"));
+
+        assert!(matches(b"a?c", b"abc"));
+        assert!(!matches(b"a?c", b"ac"));
+
+        assert!(matches(b"[abc]", b"b"));
+        assert!(!matches(b"[abc]", b"d"));
+        assert!(matches(b"[a-c]x", b"bx"));
+        assert!(matches(b"[!a-c]", b"d"));
+        assert!(!matches(b"[^a-c]", b"a"));
+        assert!(matches(b"[]]", b"]"));
+        assert!(matches(b"[a-]", b"-"));
+        assert!(matches(b"[", b"["));
+
+        assert!(matches(b"\\*", b"*"));
+        assert!(!matches(b"\\*", b"a"));
+        assert!(matches(b"[\\]]", b"]"));
+    }
+
+    #[test]
+    fn test_matches_name() {
+        assert!(!matches_name(b"*", b".hidden"));
+        assert!(matches_name(b".*", b".hidden"));
+    }
+
+    #[test]
+    fn test_component() {
+        assert!(matches!(Component::new(b"**"), Component::Recursive));
+        assert!(matches!(Component::new(b"a*"), Component::Pattern(_)));
+        assert!(
+            matches!(Component::new(b"a\\*"), Component::Literal(name) if name == OsStr::new("a*"))
+        );
+    }
+}
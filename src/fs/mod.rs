@@ -1,29 +1,64 @@
 use crate::{
     file::OpenOptions,
-    lowlevel::{self, Extensions},
+    lowlevel::{self, Extensions, FileAttrs, SftpErrorKind},
     metadata::{MetaData, MetaDataBuilder, Permissions},
+    utils::ErrorExt,
     Auxiliary, Buffer, Error, Id, OwnedHandle, WriteEnd, WriteEndWithCachedId,
 };
 
 use std::{
     borrow::Cow,
     cmp::min,
+    collections::{HashMap, VecDeque},
     convert::TryInto,
+    future::poll_fn,
+    ops::RangeBounds,
     path::{Path, PathBuf},
+    pin::Pin,
 };
 
 use bytes::BytesMut;
+use futures_core::Stream;
+use openssh_sftp_error::RemoveDirAllError;
 
 mod dir;
 pub use dir::{DirEntry, ReadDir};
 
+mod fs_stats;
+pub use fs_stats::FsStats;
+
+mod checksum;
+pub use checksum::Checksum;
+pub(crate) use checksum::{checksum_algorithms, checksum_range, ChecksumMethod};
+
+mod walk_dir;
+pub use walk_dir::{WalkDir, WalkDirEntry, WalkDirStream};
+
+mod glob;
+pub use glob::GlobStream;
+
+mod atomic_file;
+pub use atomic_file::AtomicFile;
+
+mod transfer;
+pub use transfer::{Downloader, ResumeCheck, TransferSummary, Uploader};
+
+/// Name of the extension used by [`Fs::lookup_names`].
+pub(crate) const USERS_GROUPS_BY_ID: &str = "users-groups-by-id@openssh.com";
+
+/// Name of the extension used by [`Fs::home_dir`].
+pub(crate) const HOME_DIRECTORY: &str = "home-directory";
+
 type AwaitableStatus = lowlevel::AwaitableStatus<Buffer>;
 type AwaitableAttrs = lowlevel::AwaitableAttrs<Buffer>;
+type AwaitableStatusFuture = lowlevel::AwaitableStatusFuture<Buffer>;
 type SendLinkingRequest =
     fn(&mut WriteEnd, Id, Cow<'_, Path>, Cow<'_, Path>) -> Result<AwaitableStatus, Error>;
 
 type SendRmRequest = fn(&mut WriteEnd, Id, Cow<'_, Path>) -> Result<AwaitableStatus, Error>;
 type SendMetadataRequest = fn(&mut WriteEnd, Id, Cow<'_, Path>) -> Result<AwaitableAttrs, Error>;
+type SendSetMetadataRequest =
+    fn(&mut WriteEnd, Id, Cow<'_, Path>, FileAttrs) -> Result<AwaitableStatus, Error>;
 
 /// A struct used to perform operations on remote filesystem.
 #[derive(Debug, Clone)]
@@ -69,24 +104,173 @@ impl Fs {
 
 impl Fs {
     /// Open a remote dir
+    ///
+    /// If [`Sftp::max_open_handles`](crate::sftp::Sftp::max_open_handles)
+    /// is reached, it waits until another file or directory is closed.
     pub async fn open_dir(&mut self, path: impl AsRef<Path>) -> Result<Dir, Error> {
         async fn inner(this: &mut Fs, path: &Path) -> Result<Dir, Error> {
             let path = this.concat_path_if_needed(path);
 
+            let permit = OwnedHandle::acquire_permit(&mut this.write_end).await?;
+
             this.write_end
                 .send_request(|write_end, id| Ok(write_end.send_opendir_request(id, path)?.wait()))
                 .await
-                .map(|handle| Dir(OwnedHandle::new(this.write_end.clone(), handle)))
+                .map(|handle| Dir(OwnedHandle::new(this.write_end.clone(), handle, permit)))
         }
 
         inner(self, path.as_ref()).await
     }
 
+    /// Recursively walk the remote tree rooted at `path`.
+    ///
+    /// The returned [`WalkDir`] can be configured, then converted into
+    /// a stream of entries using [`WalkDir::into_stream`].
+    ///
+    /// `path` itself is yielded as the entry at depth `0`.
+    pub fn walk_dir(&self, path: impl AsRef<Path>) -> WalkDir {
+        let root = self.concat_path_if_needed(path.as_ref()).into_owned();
+
+        WalkDir::new(self.clone(), root)
+    }
+
+    /// Return a stream of remote paths matching `pattern`, along with
+    /// their metadata.
+    ///
+    /// `pattern` supports `*`, `?` and `[...]` within a component, and
+    /// `**` as a whole component to match zero or more directories.
+    /// Wildcards can be escaped by `\`, and names starting with `.` are
+    /// only matched by a component starting with `.`.
+    ///
+    /// A leading `~` or `~user` is expanded using the expand-path
+    /// extension, otherwise [`Error::UnsupportedExtension`] is yielded.
+    ///
+    /// Only directories needed to match each component are read, and
+    /// directories that cannot be read are skipped. `**` does not follow
+    /// symlinks.
+    pub fn glob(&self, pattern: impl AsRef<Path>) -> GlobStream {
+        GlobStream::new(self.clone(), pattern.as_ref())
+    }
+
+    /// Create a builder for uploading a local tree to the remote.
+    pub fn uploader(&mut self) -> Uploader<'_> {
+        Uploader::new(self)
+    }
+
+    /// Upload the local tree rooted at `local` to `remote` using the
+    /// default options of [`Uploader`].
+    ///
+    /// See [`Uploader::upload`] for more information.
+    pub async fn upload_dir(
+        &mut self,
+        local: impl AsRef<Path>,
+        remote: impl AsRef<Path>,
+    ) -> Result<TransferSummary, Error> {
+        self.uploader().upload(local, remote).await
+    }
+
+    /// Create a builder for downloading a remote tree to local.
+    pub fn downloader(&mut self) -> Downloader<'_> {
+        Downloader::new(self)
+    }
+
+    /// Download the remote tree rooted at `remote` to `local` using the
+    /// default options of [`Downloader`].
+    ///
+    /// See [`Downloader::download`] for more information.
+    pub async fn download_dir(
+        &mut self,
+        remote: impl AsRef<Path>,
+        local: impl AsRef<Path>,
+    ) -> Result<TransferSummary, Error> {
+        self.downloader().download(remote, local).await
+    }
+
+    /// Upload local file `local` to remote file `remote`, overwriting it
+    /// if it exists.
+    ///
+    /// Up to [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`] write requests
+    /// are kept in flight at the same time, see [`File::write_all_from`]
+    /// for more information.
+    ///
+    /// Return number of bytes uploaded.
+    ///
+    /// [`File::write_all_from`]: crate::file::File::write_all_from
+    pub async fn upload_file(
+        &mut self,
+        local: impl AsRef<Path>,
+        remote: impl AsRef<Path>,
+    ) -> Result<u64, Error> {
+        transfer::upload_file(
+            self.clone(),
+            local.as_ref().to_owned(),
+            remote.as_ref().to_owned(),
+            false,
+            None,
+        )
+        .await
+    }
+
+    /// Download remote file `remote` to local file `local`, overwriting it
+    /// if it exists.
+    ///
+    /// Up to [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`] read requests
+    /// are kept in flight at the same time, see [`File::read_all_into`]
+    /// for more information.
+    ///
+    /// Return number of bytes downloaded.
+    ///
+    /// [`File::read_all_into`]: crate::file::File::read_all_into
+    pub async fn download_file(
+        &mut self,
+        remote: impl AsRef<Path>,
+        local: impl AsRef<Path>,
+    ) -> Result<u64, Error> {
+        let remote = self.concat_path_if_needed(remote.as_ref()).into_owned();
+
+        transfer::download_file(self.clone(), remote, local.as_ref().to_owned(), None, None).await
+    }
+
+    /// Upload local file `local` to `remote`, like `reput` of sftp.
+    ///
+    /// If `remote` already exists and passes `check`, the upload continues
+    /// from the end of it, otherwise `remote` is overwritten. Once done, the
+    /// mtime of `remote` is set to the mtime of `local` to mark it as
+    /// complete, and a complete `remote` is not uploaded again.
+    ///
+    /// Return number of bytes uploaded.
+    pub async fn reput(
+        &mut self,
+        local: impl AsRef<Path>,
+        remote: impl AsRef<Path>,
+        check: ResumeCheck,
+    ) -> Result<u64, Error> {
+        transfer::reput(self, local.as_ref(), remote.as_ref(), check).await
+    }
+
+    /// Download remote file `remote` to `local`, like `reget` of sftp.
+    ///
+    /// If `local` already exists and passes `check`, the download continues
+    /// from the end of it, otherwise `local` is overwritten. Once done, the
+    /// mtime of `local` is set to the mtime of `remote` to mark it as
+    /// complete, and a complete `local` is not downloaded again.
+    ///
+    /// Return number of bytes downloaded.
+    pub async fn reget(
+        &mut self,
+        remote: impl AsRef<Path>,
+        local: impl AsRef<Path>,
+        check: ResumeCheck,
+    ) -> Result<u64, Error> {
+        transfer::reget(self, remote.as_ref(), local.as_ref(), check).await
+    }
+
     /// Create a directory builder.
     pub fn dir_builder(&mut self) -> DirBuilder<'_> {
         DirBuilder {
             fs: self,
             metadata_builder: MetaDataBuilder::new(),
+            recursive: false,
         }
     }
 
@@ -99,6 +283,17 @@ impl Fs {
         inner(self, path.as_ref()).await
     }
 
+    /// Recursively create a directory and all of its missing parents.
+    ///
+    /// It is not an error if the directory already exists.
+    pub async fn create_dir_all(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        async fn inner(this: &mut Fs, path: &Path) -> Result<(), Error> {
+            this.dir_builder().recursive(true).create(path).await
+        }
+
+        inner(self, path.as_ref()).await
+    }
+
     async fn remove_impl(&mut self, path: &Path, f: SendRmRequest) -> Result<(), Error> {
         let path = self.concat_path_if_needed(path);
 
@@ -119,6 +314,117 @@ impl Fs {
             .await
     }
 
+    /// Removes a directory at `path` after removing all its contents.
+    ///
+    /// Symlinks are removed instead of being followed, and if `path` itself
+    /// is a symlink, then only the symlink is removed.
+    ///
+    /// Files and symlinks are removed using up to
+    /// [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`] pipelined requests.
+    ///
+    /// If some of the paths cannot be removed, then the rest of the tree is
+    /// still removed and [`Error::RemoveDirAll`] is returned, which lists
+    /// every path that could not be removed.
+    pub async fn remove_dir_all(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        async fn inner(this: &mut Fs, path: &Path) -> Result<(), Error> {
+            let mut walk = this.walk_dir(path).contents_first(true).into_stream();
+
+            let max_requests = lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS;
+            let mut pending: VecDeque<(PathBuf, AwaitableStatusFuture)> = VecDeque::new();
+            let mut failures = Vec::new();
+
+            // Since directories are yielded after their contents, an error
+            // reading a directory is always followed by the directory itself.
+            let mut read_dir_err = None;
+
+            while let Some(res) = poll_fn(|cx| Pin::new(&mut walk).poll_next(cx)).await {
+                let entry = match res {
+                    Ok(entry) => entry,
+                    Err(err @ Error::SftpError(..)) => {
+                        read_dir_err = Some(err);
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
+
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                let f: SendRmRequest = if is_dir {
+                    // Contents of the directory must be removed first.
+                    for (path, future) in pending.drain(..) {
+                        if let Err(err) = this.wait_for_remove(future).await? {
+                            failures.push((path, err));
+                        }
+                    }
+                    WriteEnd::send_rmdir_request
+                } else {
+                    WriteEnd::send_remove_request
+                };
+
+                let path = entry.into_path();
+
+                let id = this.write_end.get_id_mut();
+                let future = f(&mut this.write_end, id, Cow::Borrowed(&path))?.wait();
+                this.get_auxiliary().wakeup_flush_task();
+
+                // The contents could not be read, so report it as the
+                // root cause if the directory cannot be removed.
+                if is_dir {
+                    if let Some(err) = read_dir_err.take() {
+                        if this.wait_for_remove(future).await?.is_err() {
+                            failures.push((path, err));
+                        }
+                        continue;
+                    }
+                }
+
+                pending.push_back((path, future));
+
+                if pending.len() >= max_requests {
+                    let (path, future) = pending.pop_front().unwrap();
+                    if let Err(err) = this.wait_for_remove(future).await? {
+                        failures.push((path, err));
+                    }
+                }
+            }
+
+            for (path, future) in pending {
+                if let Err(err) = this.wait_for_remove(future).await? {
+                    failures.push((path, err));
+                }
+            }
+
+            if let Some(err) = read_dir_err {
+                // Failed to get metadata of `path`.
+                Err(err)
+            } else if failures.is_empty() {
+                Ok(())
+            } else {
+                Err(Error::RemoveDirAll(Box::new(RemoveDirAllError {
+                    failures,
+                })))
+            }
+        }
+
+        inner(self, path.as_ref()).await
+    }
+
+    /// Wait for the response of a remove request.
+    ///
+    /// Return `Ok(Err(_))` if the server failed to remove the path.
+    async fn wait_for_remove(
+        &mut self,
+        future: AwaitableStatusFuture,
+    ) -> Result<Result<(), Error>, Error> {
+        match self.write_end.cancel_if_task_failed(future).await {
+            Ok((id, ())) => {
+                self.write_end.cache_id_mut(id);
+                Ok(Ok(()))
+            }
+            Err(err @ Error::SftpError(..)) => Ok(Err(err)),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Returns the canonical, absolute form of a path with all intermediate
     /// components normalized and symbolic links resolved.
     ///
@@ -234,6 +540,125 @@ impl Fs {
         inner(self, from.as_ref(), to.as_ref()).await
     }
 
+    /// Copies the contents of file `src` to `dst` on the remote, returning
+    /// the method used.
+    ///
+    /// If `overwrite` is `false` and `dst` already exists, it fails.
+    ///
+    /// It tries, in order:
+    ///  - the `copy-file` extension, which copies the whole file in
+    ///    one request;
+    ///  - the `copy-data` extension, which requires opening both files;
+    ///  - pipelined read and write requests, which transfers the data
+    ///    over the network twice.
+    ///
+    /// NOTE that opening both files requires two slots in
+    /// [`Sftp::max_open_handles`](crate::sftp::Sftp::max_open_handles).
+    pub async fn copy(
+        &mut self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        overwrite: bool,
+    ) -> Result<CopyMethod, Error> {
+        self.copy_impl(src.as_ref(), dst.as_ref(), overwrite, false)
+            .await
+    }
+
+    /// Same as [`Fs::copy`], except that the permissions of `src`
+    /// are also copied to `dst`.
+    pub async fn copy_with_permissions(
+        &mut self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        overwrite: bool,
+    ) -> Result<CopyMethod, Error> {
+        self.copy_impl(src.as_ref(), dst.as_ref(), overwrite, true)
+            .await
+    }
+
+    async fn copy_impl(
+        &mut self,
+        src: &Path,
+        dst: &Path,
+        overwrite: bool,
+        preserve_permissions: bool,
+    ) -> Result<CopyMethod, Error> {
+        let src = self.concat_path_if_needed(src);
+        let dst = self.concat_path_if_needed(dst);
+
+        let method = if self.write_end.server_extension_data("copy-file").is_some() {
+            let (src, dst) = (Cow::Borrowed(&*src), Cow::Borrowed(&*dst));
+
+            self.write_end
+                .send_request(|write_end, id| {
+                    Ok(write_end
+                        .send_copy_file_request(id, src, dst, overwrite)?
+                        .wait())
+                })
+                .await?;
+
+            CopyMethod::CopyFile
+        } else {
+            let mut src_file = OpenOptions::open_inner(
+                lowlevel::OpenOptions::new().read(true),
+                false,
+                false,
+                false,
+                &src,
+                self.write_end.clone(),
+            )
+            .await?;
+
+            // `dst` is not truncated on open, since it might be the same
+            // file as `src`; it is truncated to the copied len instead.
+            let mut dst_file = OpenOptions::open_inner(
+                lowlevel::OpenOptions::new().write(true),
+                false,
+                overwrite,
+                !overwrite,
+                &dst,
+                self.write_end.clone(),
+            )
+            .await?;
+
+            let (method, copied) = if self
+                .get_auxiliary()
+                .extensions()
+                .contains(Extensions::COPY_DATA)
+            {
+                src_file.copy_all_to(&mut dst_file).await?;
+                let copied = src_file
+                    .metadata()
+                    .await?
+                    .len()
+                    .ok_or(Error::InvalidResponse(
+                        &"Server did not return the size of the copied file",
+                    ))?;
+                (CopyMethod::CopyData, copied)
+            } else {
+                let copied = src_file.copy_all_to_pipelined(&mut dst_file).await?;
+                (CopyMethod::ReadWrite, copied)
+            };
+
+            if overwrite {
+                dst_file.set_len(copied).await?;
+            }
+
+            src_file.close().await?;
+            dst_file.close().await?;
+
+            method
+        };
+
+        if preserve_permissions {
+            if let Some(perm) = self.metadata(&*src).await?.permissions() {
+                self.set_permissions(&*dst, perm).await?;
+            }
+        }
+
+        Ok(method)
+    }
+
     /// Reads a symbolic link, returning the file that the link points to.
     pub async fn read_link(&mut self, path: impl AsRef<Path>) -> Result<PathBuf, Error> {
         async fn inner(this: &mut Fs, path: &Path) -> Result<PathBuf, Error> {
@@ -248,15 +673,16 @@ impl Fs {
         inner(self, path.as_ref()).await
     }
 
-    async fn set_metadata_impl(&mut self, path: &Path, metadata: MetaData) -> Result<(), Error> {
+    async fn set_metadata_impl(
+        &mut self,
+        path: &Path,
+        metadata: MetaData,
+        f: SendSetMetadataRequest,
+    ) -> Result<(), Error> {
         let path = self.concat_path_if_needed(path);
 
         self.write_end
-            .send_request(|write_end, id| {
-                Ok(write_end
-                    .send_setstat_request(id, path, metadata.into_inner())?
-                    .wait())
-            })
+            .send_request(|write_end, id| Ok(f(write_end, id, path, metadata.into_inner())?.wait()))
             .await
     }
 
@@ -266,7 +692,39 @@ impl Fs {
         path: impl AsRef<Path>,
         metadata: MetaData,
     ) -> Result<(), Error> {
-        self.set_metadata_impl(path.as_ref(), metadata).await
+        self.set_metadata_impl(path.as_ref(), metadata, WriteEnd::send_setstat_request)
+            .await
+    }
+
+    /// Change the metadata of a file, a directory or a symlink.
+    ///
+    /// Unlike [`Fs::set_metadata`], this does not follow symlink, so the
+    /// metadata of the symlink itself is changed.
+    ///
+    /// # Precondition
+    ///
+    /// Require extension `lsetstat`
+    ///
+    /// You can check it with [`Sftp::support_lsetstat`](crate::sftp::Sftp::support_lsetstat).
+    pub async fn set_symlink_metadata(
+        &mut self,
+        path: impl AsRef<Path>,
+        metadata: MetaData,
+    ) -> Result<(), Error> {
+        async fn inner(this: &mut Fs, path: &Path, metadata: MetaData) -> Result<(), Error> {
+            if !this
+                .get_auxiliary()
+                .extensions()
+                .contains(Extensions::LSETSTAT)
+            {
+                return Err(Error::UnsupportedExtension(&"lsetstat"));
+            }
+
+            this.set_metadata_impl(path, metadata, WriteEnd::send_lsetstat_request)
+                .await
+        }
+
+        inner(self, path.as_ref(), metadata).await
     }
 
     /// Changes the permissions found on a file or a directory.
@@ -276,8 +734,12 @@ impl Fs {
         perm: Permissions,
     ) -> Result<(), Error> {
         async fn inner(this: &mut Fs, path: &Path, perm: Permissions) -> Result<(), Error> {
-            this.set_metadata_impl(path, MetaDataBuilder::new().permissions(perm).create())
-                .await
+            this.set_metadata_impl(
+                path,
+                MetaDataBuilder::new().permissions(perm).create(),
+                WriteEnd::send_setstat_request,
+            )
+            .await
         }
 
         inner(self, path.as_ref(), perm).await
@@ -293,7 +755,7 @@ impl Fs {
         self.write_end
             .send_request(|write_end, id| Ok(f(write_end, id, path)?.wait()))
             .await
-            .map(MetaData::new)
+            .map(MetaData::from_attrs)
     }
 
     /// Given a path, queries the file system to get information about a file,
@@ -309,6 +771,258 @@ impl Fs {
             .await
     }
 
+    /// Returns statistics of the filesystem containing `path`.
+    ///
+    /// # Precondition
+    ///
+    /// Require extension `statvfs`
+    ///
+    /// You can check it with [`Sftp::support_statvfs`](crate::sftp::Sftp::support_statvfs).
+    pub async fn statvfs(&mut self, path: impl AsRef<Path>) -> Result<FsStats, Error> {
+        async fn inner(this: &mut Fs, path: &Path) -> Result<FsStats, Error> {
+            if !this
+                .get_auxiliary()
+                .extensions()
+                .contains(Extensions::STATVFS)
+            {
+                return Err(Error::UnsupportedExtension(&"statvfs"));
+            }
+
+            let path = this.concat_path_if_needed(path);
+
+            this.write_end
+                .send_request(|write_end, id| Ok(write_end.send_statvfs_request(id, path)?.wait()))
+                .await
+                .map(FsStats::new)
+        }
+
+        inner(self, path.as_ref()).await
+    }
+
+    /// Returns hashes of the file at `path` calculated by the server.
+    ///
+    /// * `algorithms` - comma-separated list of hash algorithms,
+    ///   e.g. `"sha256,md5"`, the server uses the first one it supports.
+    /// * `range` - range of the file to hash, unbounded end means
+    ///   up to the end of the file.
+    /// * `block_size` - `0` returns one hash for the whole range, otherwise
+    ///   the range is split into blocks of `block_size` bytes (at least 256)
+    ///   and one hash is returned for each block.
+    ///
+    /// # Precondition
+    ///
+    /// Require extension `check-file`, or extension `md5-hash` if `algorithms`
+    /// contains `md5` and `block_size` is `0`.
+    ///
+    /// You can check the algorithms supported with
+    /// [`Sftp::checksum_algorithms`](crate::sftp::Sftp::checksum_algorithms).
+    pub async fn checksum(
+        &mut self,
+        path: impl AsRef<Path>,
+        algorithms: &str,
+        range: impl RangeBounds<u64>,
+        block_size: u32,
+    ) -> Result<Checksum, Error> {
+        async fn inner(
+            this: &mut Fs,
+            path: &Path,
+            algorithms: &str,
+            (offset, len): (u64, u64),
+            block_size: u32,
+        ) -> Result<Checksum, Error> {
+            let method = ChecksumMethod::new(&this.write_end, algorithms, block_size, "md5-hash")?;
+
+            let path = this.concat_path_if_needed(path);
+
+            match method {
+                ChecksumMethod::CheckFile => this
+                    .write_end
+                    .send_request(|write_end, id| {
+                        Ok(write_end
+                            .send_check_file_name_request(
+                                id, path, algorithms, offset, len, block_size,
+                            )?
+                            .wait())
+                    })
+                    .await
+                    .map(Checksum::new),
+                ChecksumMethod::Md5Hash => this
+                    .write_end
+                    .send_request(|write_end, id| {
+                        Ok(write_end
+                            .send_md5_hash_request(id, path, offset, len, &[])?
+                            .wait())
+                    })
+                    .await
+                    .map(Checksum::from_md5_hash),
+            }
+        }
+
+        let range = checksum_range(range)?;
+
+        inner(self, path.as_ref(), algorithms, range, block_size).await
+    }
+
+    /// Resolve `uids` and `gids` to user and group names on the remote.
+    ///
+    /// Returns the names of users and groups keyed by their ids.
+    /// Ids that the server fails to resolve are left out.
+    ///
+    /// If [`SftpOptions::cache_names`](crate::SftpOptions::cache_names)
+    /// is enabled, only ids that have not been looked up before are
+    /// sent to the server.
+    ///
+    /// # Precondition
+    ///
+    /// Require extension `users-groups-by-id`, which can be checked with
+    /// [`Sftp::support_users_groups_by_id`](crate::sftp::Sftp::support_users_groups_by_id).
+    pub async fn lookup_names(
+        &mut self,
+        uids: &[u32],
+        gids: &[u32],
+    ) -> Result<(HashMap<u32, Box<str>>, HashMap<u32, Box<str>>), Error> {
+        let mut users = HashMap::new();
+        let mut groups = HashMap::new();
+
+        let mut uids = uids.to_vec();
+        let mut gids = gids.to_vec();
+
+        if let Some(cache) = self.get_auxiliary().names_cache() {
+            let cache = cache.lock().unwrap();
+
+            uids.retain(|uid| match cache.users.get(uid) {
+                Some(name) => {
+                    users.extend(name.clone().map(|name| (*uid, name)));
+                    false
+                }
+                None => true,
+            });
+            gids.retain(|gid| match cache.groups.get(gid) {
+                Some(name) => {
+                    groups.extend(name.clone().map(|name| (*gid, name)));
+                    false
+                }
+                None => true,
+            });
+        }
+
+        uids.sort_unstable();
+        uids.dedup();
+        gids.sort_unstable();
+        gids.dedup();
+
+        if uids.is_empty() && gids.is_empty() {
+            return Ok((users, groups));
+        }
+
+        if self
+            .write_end
+            .server_extension_data(USERS_GROUPS_BY_ID)
+            .is_none()
+        {
+            return Err(Error::UnsupportedExtension(&"users-groups-by-id"));
+        }
+
+        let names = self
+            .write_end
+            .send_request(|write_end, id| {
+                Ok(write_end
+                    .send_users_groups_by_id_request(id, &uids, &gids)?
+                    .wait())
+            })
+            .await?;
+
+        if names.users.len() != uids.len() || names.groups.len() != gids.len() {
+            return Err(Error::InvalidResponse(
+                &"Number of names does not match number of ids requested",
+            ));
+        }
+
+        let resolved = |name: Box<str>| (!name.is_empty()).then_some(name);
+
+        let users_resolved = uids.into_iter().zip(Vec::from(names.users));
+        let groups_resolved = gids.into_iter().zip(Vec::from(names.groups));
+
+        if let Some(cache) = self.get_auxiliary().names_cache() {
+            let mut cache = cache.lock().unwrap();
+
+            for (uid, name) in users_resolved {
+                let name = resolved(name);
+                users.extend(name.clone().map(|name| (uid, name)));
+                cache.users.insert(uid, name);
+            }
+            for (gid, name) in groups_resolved {
+                let name = resolved(name);
+                groups.extend(name.clone().map(|name| (gid, name)));
+                cache.groups.insert(gid, name);
+            }
+        } else {
+            users.extend(users_resolved.filter_map(|(uid, name)| Some((uid, resolved(name)?))));
+            groups.extend(groups_resolved.filter_map(|(gid, name)| Some((gid, resolved(name)?))));
+        }
+
+        Ok((users, groups))
+    }
+
+    /// Return the home directory of `username`, or of the user the remote
+    /// `sftp-server` runs as if `username` is `None`, along with the
+    /// [`HomeDirMethod`] used to find it.
+    ///
+    /// It uses the `home-directory` extension if the server supports it,
+    /// which can be checked with
+    /// [`Sftp::support_home_directory`](crate::sftp::Sftp::support_home_directory),
+    /// otherwise it falls back to expanding `~` or `~username` with
+    /// the `expand-path` extension.
+    ///
+    /// If neither is supported and `username` is `None`, it resolves `.`
+    /// instead, since `sftp-server` starts in the home directory.
+    /// If `username` is `Some`, it fails with [`Error::UnsupportedExtension`].
+    pub async fn home_dir(
+        &mut self,
+        username: Option<&str>,
+    ) -> Result<(PathBuf, HomeDirMethod), Error> {
+        let username = username.unwrap_or("");
+
+        if self
+            .write_end
+            .server_extension_data(HOME_DIRECTORY)
+            .is_some()
+        {
+            return self
+                .write_end
+                .send_request(|write_end, id| {
+                    Ok(write_end.send_home_directory_request(id, username)?.wait())
+                })
+                .await
+                .map(|path| (path.into(), HomeDirMethod::HomeDirectory));
+        }
+
+        let (path, method): (Cow<'_, Path>, _) = if self
+            .get_auxiliary()
+            .extensions()
+            .contains(Extensions::EXPAND_PATH)
+        {
+            (
+                PathBuf::from(format!("~{username}")).into(),
+                HomeDirMethod::ExpandPath,
+            )
+        } else if username.is_empty() {
+            (Path::new(".").into(), HomeDirMethod::Realpath)
+        } else {
+            return Err(Error::UnsupportedExtension(&"home-directory"));
+        };
+
+        let f = match method {
+            HomeDirMethod::ExpandPath => WriteEnd::send_expand_path_request,
+            _ => WriteEnd::send_realpath_request,
+        };
+
+        self.write_end
+            .send_request(|write_end, id| Ok(f(write_end, id, path)?.wait()))
+            .await
+            .map(|path| (path.into(), method))
+    }
+
     /// Reads the entire contents of a file into a bytes.
     pub async fn read(&mut self, path: impl AsRef<Path>) -> Result<BytesMut, Error> {
         async fn inner(this: &mut Fs, path: &Path) -> Result<BytesMut, Error> {
@@ -389,6 +1103,68 @@ impl Fs {
 
         inner(self, path.as_ref(), content.as_ref()).await
     }
+
+    /// Create an [`AtomicFile`], which replaces `path` once committed.
+    ///
+    /// It is the incremental counterpart of [`Fs::write_atomic`], for content
+    /// that is not available all at once.
+    ///
+    /// # Precondition
+    ///
+    /// Require extension `posix-rename`
+    ///
+    /// You can check it with [`Sftp::support_posix_rename`](crate::sftp::Sftp::support_posix_rename).
+    pub async fn create_atomic(&self, path: impl AsRef<Path>) -> Result<AtomicFile, Error> {
+        AtomicFile::create(self, path.as_ref()).await
+    }
+
+    /// Write the entire `contents` into a temporary file, then atomically
+    /// replace `path` with it, so readers never see a partially written file.
+    ///
+    /// See [`AtomicFile`] for more information.
+    ///
+    /// # Precondition
+    ///
+    /// Require extension `posix-rename`
+    ///
+    /// You can check it with [`Sftp::support_posix_rename`](crate::sftp::Sftp::support_posix_rename).
+    pub async fn write_atomic(
+        &mut self,
+        path: impl AsRef<Path>,
+        content: impl AsRef<[u8]>,
+    ) -> Result<(), Error> {
+        async fn inner(this: &mut Fs, path: &Path, content: &[u8]) -> Result<(), Error> {
+            let mut file = this.create_atomic(path).await?;
+            file.write_all(content).await?;
+            file.commit().await
+        }
+
+        inner(self, path.as_ref(), content.as_ref()).await
+    }
+}
+
+/// Method used by [`Fs::home_dir`] to find the home directory.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum HomeDirMethod {
+    /// The `home-directory` extension.
+    HomeDirectory,
+    /// Tilde expansion using the `expand-path` extension.
+    ExpandPath,
+    /// Canonicalize `.` using realpath.
+    Realpath,
+}
+
+/// Method used by [`Fs::copy`] to copy the file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum CopyMethod {
+    /// The `copy-file` extension.
+    CopyFile,
+    /// The `copy-data` extension.
+    CopyData,
+    /// Pipelined read and write requests.
+    ReadWrite,
 }
 
 /// Remote Directory
@@ -413,16 +1189,34 @@ impl Dir {
 pub struct DirBuilder<'a> {
     fs: &'a mut Fs,
     metadata_builder: MetaDataBuilder,
+    recursive: bool,
 }
 
 impl DirBuilder<'_> {
     /// Reset builder back to default.
     pub fn reset(&mut self) -> &mut Self {
         self.metadata_builder = MetaDataBuilder::new();
+        self.recursive = false;
+        self
+    }
+
+    /// Create all missing parent directories of the dir to be built,
+    /// using the same permissions and id.
+    ///
+    /// If set to `true`, then it is not an error if the dir already exists.
+    ///
+    /// The id set by [`DirBuilder::id`] is applied to every dir created
+    /// with an extra setstat, since sftp-server ignores it in mkdir.
+    /// If that fails, the dir is removed again.
+    pub fn recursive(&mut self, recursive: bool) -> &mut Self {
+        self.recursive = recursive;
         self
     }
 
     /// Set id of the dir to be built.
+    ///
+    /// Sending it fails if the negotiated sftp version is 4 or later,
+    /// since the server takes owner and group names instead.
     pub fn id(&mut self, (uid, gid): (u32, u32)) -> &mut Self {
         self.metadata_builder.id((uid, gid));
         self
@@ -444,13 +1238,111 @@ impl DirBuilder<'_> {
             let path = fs.concat_path_if_needed(path);
             let attrs = this.metadata_builder.create().into_inner();
 
-            fs.write_end
-                .send_request(|write_end, id| {
-                    Ok(write_end.send_mkdir_request(id, path, attrs)?.wait())
-                })
-                .await
+            if !this.recursive {
+                return mkdir(fs, &path, attrs).await;
+            }
+
+            // Missing directories, starting from `path`.
+            let mut missing = Vec::new();
+            let mut curr: &Path = &path;
+
+            loop {
+                match mkdir(fs, curr, attrs).await {
+                    Ok(()) => {
+                        set_id_or_remove(fs, curr, attrs).await?;
+                        break;
+                    }
+                    Err(err @ Error::SftpError(SftpErrorKind::NoSuchFile, _)) => {
+                        missing.push(curr);
+                        curr = match curr.parent() {
+                            Some(parent) if !parent.as_os_str().is_empty() => parent,
+                            _ => return Err(err),
+                        };
+                    }
+                    Err(err) => {
+                        // `curr` already exists, possibly created by another
+                        // client after the mkdir of its child failed, so
+                        // the missing dirs below it still need creating.
+                        ensure_is_dir(fs, curr, err).await?;
+                        break;
+                    }
+                }
+            }
+
+            // Create the missing parents in order.
+            for curr in missing.into_iter().rev() {
+                match mkdir(fs, curr, attrs).await {
+                    Ok(()) => set_id_or_remove(fs, curr, attrs).await?,
+                    // Another client could have created it.
+                    Err(err) => ensure_is_dir(fs, curr, err).await?,
+                }
+            }
+
+            Ok(())
         }
 
         inner(self, path.as_ref()).await
     }
 }
+
+async fn mkdir(fs: &mut Fs, path: &Path, attrs: FileAttrs) -> Result<(), Error> {
+    fs.write_end
+        .send_request(|write_end, id| {
+            Ok(write_end
+                .send_mkdir_request(id, Cow::Borrowed(path), attrs)?
+                .wait())
+        })
+        .await
+}
+
+/// Change owner of the dir created at `path` if `attrs` contains an id,
+/// since sftp-server ignores uid and gid passed to mkdir.
+///
+/// If it fails, the dir is removed so that it is not left with
+/// the wrong owner.
+async fn set_id_or_remove(fs: &mut Fs, path: &Path, attrs: FileAttrs) -> Result<(), Error> {
+    let err = match set_id(fs, path, attrs).await {
+        Ok(()) => return Ok(()),
+        Err(err) => err,
+    };
+
+    let res = fs
+        .write_end
+        .send_request(|write_end, id| {
+            Ok(write_end
+                .send_rmdir_request(id, Cow::Borrowed(path))?
+                .wait())
+        })
+        .await;
+
+    match res {
+        Ok(()) => Err(err),
+        Err(rmdir_err) => Err(err.error_on_cleanup(rmdir_err)),
+    }
+}
+
+async fn set_id(fs: &mut Fs, path: &Path, attrs: FileAttrs) -> Result<(), Error> {
+    let (uid, gid) = match attrs.get_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    let mut attrs = FileAttrs::new();
+    attrs.set_id(uid, gid);
+
+    fs.write_end
+        .send_request(|write_end, id| {
+            Ok(write_end
+                .send_setstat_request(id, Cow::Borrowed(path), attrs)?
+                .wait())
+        })
+        .await
+}
+
+/// Return `Ok(())` if `path` is a directory, otherwise `Err(err)`.
+async fn ensure_is_dir(fs: &mut Fs, path: &Path, err: Error) -> Result<(), Error> {
+    match fs.metadata(path).await {
+        Ok(metadata) if metadata.file_type().map(|t| t.is_dir()).unwrap_or(false) => Ok(()),
+        _ => Err(err),
+    }
+}
@@ -0,0 +1,1006 @@
+use crate::{
+    file::{File, OpenOptions},
+    lowlevel,
+    metadata::{MetaData, MetaDataBuilder, Permissions},
+    Error, Progress, UnixTimeStamp,
+};
+
+use super::{checksum_algorithms, walk_dir::loop_error, Fs};
+
+use std::{
+    borrow::Cow,
+    cmp::min,
+    collections::VecDeque,
+    fs::Metadata,
+    future::poll_fn,
+    io::{self, SeekFrom},
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    time::SystemTime,
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use bytes::BytesMut;
+use filetime::FileTime;
+use futures_core::Stream;
+use md5::{Digest, Md5};
+
+use tokio::{
+    fs as local_fs,
+    io::{AsyncReadExt, AsyncSeekExt},
+    task::JoinHandle,
+};
+
+/// Number of bytes hashed at a time by [`ResumeCheck::TailChecksum`].
+const TAIL_CHUNK_LEN: usize = 64 * 1024;
+
+/// Default value of [`Uploader::max_concurrent_files`] and
+/// [`Downloader::max_concurrent_files`].
+const DEFAULT_MAX_CONCURRENT_FILES: usize = 8;
+
+/// Summary of a recursive transfer, returned by [`Uploader::upload`]
+/// and [`Downloader::download`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TransferSummary {
+    files: u64,
+    dirs: u64,
+    symlinks: u64,
+    bytes: u64,
+}
+
+impl TransferSummary {
+    /// Returns the number of regular files transferred.
+    pub fn files(&self) -> u64 {
+        self.files
+    }
+
+    /// Returns the number of directories transferred.
+    pub fn dirs(&self) -> u64 {
+        self.dirs
+    }
+
+    /// Returns the number of symlinks recreated.
+    pub fn symlinks(&self) -> u64 {
+        self.symlinks
+    }
+
+    /// Returns the number of bytes transferred.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+/// Builder for uploading a local tree to the remote, created by
+/// [`Fs::uploader`].
+#[derive(Debug)]
+pub struct Uploader<'a> {
+    fs: &'a mut Fs,
+    max_concurrent_files: NonZeroUsize,
+    follow_links: bool,
+    preserve: bool,
+    progress: Option<Progress>,
+}
+
+impl<'a> Uploader<'a> {
+    pub(super) fn new(fs: &'a mut Fs) -> Self {
+        Self {
+            fs,
+            max_concurrent_files: NonZeroUsize::new(DEFAULT_MAX_CONCURRENT_FILES).unwrap(),
+            follow_links: false,
+            preserve: false,
+            progress: None,
+        }
+    }
+}
+
+impl Uploader<'_> {
+    /// Reset builder back to default.
+    pub fn reset(&mut self) -> &mut Self {
+        self.max_concurrent_files = NonZeroUsize::new(DEFAULT_MAX_CONCURRENT_FILES).unwrap();
+        self.follow_links = false;
+        self.preserve = false;
+        self.progress = None;
+        self
+    }
+
+    /// Set the maximum number of files uploaded at the same time.
+    ///
+    /// Each of them takes up an open handle, which counts towards
+    /// [`Sftp::max_open_handles`](crate::sftp::Sftp::max_open_handles).
+    ///
+    /// Default to `8`.
+    pub fn max_concurrent_files(&mut self, max_concurrent_files: NonZeroUsize) -> &mut Self {
+        self.max_concurrent_files = max_concurrent_files;
+        self
+    }
+
+    /// Upload the targets of local symlinks instead of recreating the
+    /// symlinks on the remote if set to `true`.
+    ///
+    /// Default to `false`.
+    pub fn follow_links(&mut self, follow_links: bool) -> &mut Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Keep the permissions and modification time of files and directories,
+    /// like `put -p` of sftp, if set to `true`.
+    ///
+    /// Default to `false`.
+    pub fn preserve(&mut self, preserve: bool) -> &mut Self {
+        self.preserve = preserve;
+        self
+    }
+
+    /// Report bytes uploaded to `progress`.
+    ///
+    /// The total is only set if a single file is uploaded.
+    pub fn progress(&mut self, progress: Progress) -> &mut Self {
+        self.progress = Some(progress);
+        self
+    }
+}
+
+impl Uploader<'_> {
+    /// Upload the local tree rooted at `local` to `remote`, like `put -r`
+    /// of sftp.
+    ///
+    /// Missing parents of `remote` are created and existing directories are
+    /// reused, while existing files are overwritten.
+    ///
+    /// Files that are neither regular files, directories nor symlinks
+    /// (e.g. sockets) are skipped.
+    ///
+    /// It stops at the first error, in which case some of the files might
+    /// already be uploaded.
+    pub async fn upload(
+        &mut self,
+        local: impl AsRef<Path>,
+        remote: impl AsRef<Path>,
+    ) -> Result<TransferSummary, Error> {
+        async fn inner(
+            this: &mut Uploader<'_>,
+            local: &Path,
+            remote: &Path,
+        ) -> Result<TransferSummary, Error> {
+            let mut summary = TransferSummary::default();
+
+            // Abort uploads of files if the upload is cancelled or failed.
+            let mut tasks = scopeguard::guard(VecDeque::new(), |tasks| {
+                tasks.iter().for_each(JoinHandle::abort)
+            });
+
+            // Metadata of directories is set after their contents are
+            // uploaded, since it could change the mtime or make them read-only.
+            let mut dirs = Vec::new();
+
+            // Canonical path of the ancestors of the current entry, used to
+            // detect loops when following symlinks.
+            let mut ancestors: Vec<PathBuf> = Vec::new();
+
+            let mut stack = vec![(local.to_owned(), remote.to_owned(), 0)];
+
+            while let Some((local, remote, depth)) = stack.pop() {
+                let metadata = if this.follow_links {
+                    local_fs::metadata(&local).await?
+                } else {
+                    local_fs::symlink_metadata(&local).await?
+                };
+                let file_type = metadata.file_type();
+
+                if file_type.is_symlink() {
+                    let target = local_fs::read_link(&local).await?;
+                    symlink(this.fs, &target, &remote).await?;
+
+                    summary.symlinks += 1;
+                } else if file_type.is_dir() {
+                    if this.follow_links {
+                        let canonical = local_fs::canonicalize(&local).await?;
+
+                        ancestors.truncate(depth);
+                        if ancestors.contains(&canonical) {
+                            return Err(loop_error(&local, &canonical).into());
+                        }
+                        ancestors.push(canonical);
+                    }
+
+                    this.fs.create_dir_all(&remote).await?;
+
+                    let mut read_dir = local_fs::read_dir(&local).await?;
+                    let mut entries = Vec::new();
+                    while let Some(entry) = read_dir.next_entry().await? {
+                        let filename = entry.file_name();
+                        entries.push((entry.path(), remote.join(filename), depth + 1));
+                    }
+                    stack.extend(entries.into_iter().rev());
+
+                    if this.preserve {
+                        dirs.push((remote, metadata));
+                    }
+                    summary.dirs += 1;
+                } else if file_type.is_file() {
+                    let fs = this.fs.clone();
+                    let preserve = this.preserve;
+                    let progress = this.progress.clone();
+
+                    if let (Some(progress), 0) = (&progress, depth) {
+                        progress.set_total(Some(metadata.len()));
+                    }
+
+                    let task = this
+                        .fs
+                        .get_auxiliary()
+                        .tokio_handle()
+                        .spawn(upload_file(fs, local, remote, preserve, progress));
+                    tasks.push_back(task);
+
+                    if tasks.len() >= this.max_concurrent_files.get() {
+                        summary.bytes += tasks.pop_front().unwrap().await??;
+                    }
+                    summary.files += 1;
+                }
+            }
+
+            while let Some(task) = tasks.pop_front() {
+                summary.bytes += task.await??;
+            }
+
+            for (remote, metadata) in dirs.into_iter().rev() {
+                this.fs
+                    .set_metadata(&remote, to_metadata(&metadata))
+                    .await?;
+            }
+
+            Ok(summary)
+        }
+
+        inner(self, local.as_ref(), remote.as_ref()).await
+    }
+}
+
+/// Builder for downloading a remote tree to local, created by
+/// [`Fs::downloader`].
+#[derive(Debug)]
+pub struct Downloader<'a> {
+    fs: &'a mut Fs,
+    max_concurrent_files: NonZeroUsize,
+    follow_links: bool,
+    preserve: bool,
+    progress: Option<Progress>,
+}
+
+impl<'a> Downloader<'a> {
+    pub(super) fn new(fs: &'a mut Fs) -> Self {
+        Self {
+            fs,
+            max_concurrent_files: NonZeroUsize::new(DEFAULT_MAX_CONCURRENT_FILES).unwrap(),
+            follow_links: false,
+            preserve: false,
+            progress: None,
+        }
+    }
+}
+
+impl Downloader<'_> {
+    /// Reset builder back to default.
+    pub fn reset(&mut self) -> &mut Self {
+        self.max_concurrent_files = NonZeroUsize::new(DEFAULT_MAX_CONCURRENT_FILES).unwrap();
+        self.follow_links = false;
+        self.preserve = false;
+        self.progress = None;
+        self
+    }
+
+    /// Set the maximum number of files downloaded at the same time.
+    ///
+    /// Each of them takes up an open handle, which counts towards
+    /// [`Sftp::max_open_handles`](crate::sftp::Sftp::max_open_handles).
+    ///
+    /// Default to `8`.
+    pub fn max_concurrent_files(&mut self, max_concurrent_files: NonZeroUsize) -> &mut Self {
+        self.max_concurrent_files = max_concurrent_files;
+        self
+    }
+
+    /// Download the targets of remote symlinks instead of recreating the
+    /// symlinks locally if set to `true`.
+    ///
+    /// See [`WalkDir::follow_links`](super::WalkDir::follow_links) for
+    /// more information.
+    ///
+    /// Default to `false`.
+    pub fn follow_links(&mut self, follow_links: bool) -> &mut Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Keep the permissions, access time and modification time of files and
+    /// directories, like `get -p` of sftp, if set to `true`.
+    ///
+    /// Default to `false`.
+    pub fn preserve(&mut self, preserve: bool) -> &mut Self {
+        self.preserve = preserve;
+        self
+    }
+
+    /// Report bytes downloaded to `progress`.
+    ///
+    /// The total is only set if a single file is downloaded.
+    pub fn progress(&mut self, progress: Progress) -> &mut Self {
+        self.progress = Some(progress);
+        self
+    }
+}
+
+impl Downloader<'_> {
+    /// Download the remote tree rooted at `remote` to `local`, like `get -r`
+    /// of sftp.
+    ///
+    /// Missing parents of `local` are created and existing directories are
+    /// reused, while existing files are overwritten.
+    ///
+    /// Files that are neither regular files, directories nor symlinks
+    /// (e.g. sockets) are skipped.
+    ///
+    /// It stops at the first error, in which case some of the files might
+    /// already be downloaded.
+    pub async fn download(
+        &mut self,
+        remote: impl AsRef<Path>,
+        local: impl AsRef<Path>,
+    ) -> Result<TransferSummary, Error> {
+        async fn inner(
+            this: &mut Downloader<'_>,
+            remote: &Path,
+            local: &Path,
+        ) -> Result<TransferSummary, Error> {
+            let mut summary = TransferSummary::default();
+
+            // Abort downloads of files if the download is cancelled or failed.
+            let mut tasks = scopeguard::guard(VecDeque::new(), |tasks| {
+                tasks.iter().for_each(JoinHandle::abort)
+            });
+
+            // Metadata of directories is set after their contents are
+            // downloaded, since it could change the mtime or make them read-only.
+            let mut dirs = Vec::new();
+
+            let root = this.fs.concat_path_if_needed(remote).into_owned();
+            let mut walk = this
+                .fs
+                .walk_dir(remote)
+                .follow_links(this.follow_links)
+                .into_stream();
+
+            // Paths of entries are already joined with the cwd.
+            let mut fs = this.fs.clone();
+            fs.set_cwd("");
+
+            while let Some(entry) = poll_fn(|cx| Pin::new(&mut walk).poll_next(cx)).await {
+                let entry = entry?;
+
+                let relative = relative_path(&root, entry.path(), entry.depth())?;
+                let local = if relative.as_os_str().is_empty() {
+                    local.to_owned()
+                } else {
+                    local.join(relative)
+                };
+
+                let file_type = match entry.file_type() {
+                    Some(file_type) => file_type,
+                    None => continue,
+                };
+
+                if file_type.is_symlink() {
+                    let target = fs.read_link(entry.path()).await?;
+                    local_symlink(&target, &local).await?;
+
+                    summary.symlinks += 1;
+                } else if file_type.is_dir() {
+                    local_fs::create_dir_all(&local).await?;
+
+                    if this.preserve {
+                        dirs.push((local, entry.metadata().clone()));
+                    }
+                    summary.dirs += 1;
+                } else if file_type.is_file() {
+                    let metadata = if this.preserve {
+                        Some(entry.metadata().clone())
+                    } else {
+                        None
+                    };
+
+                    let progress = this.progress.clone();
+                    if let (Some(progress), 0) = (&progress, entry.depth()) {
+                        progress.set_total(entry.metadata().len());
+                    }
+
+                    let task = fs.get_auxiliary().tokio_handle().spawn(download_file(
+                        fs.clone(),
+                        entry.into_path(),
+                        local,
+                        metadata,
+                        progress,
+                    ));
+                    tasks.push_back(task);
+
+                    if tasks.len() >= this.max_concurrent_files.get() {
+                        summary.bytes += tasks.pop_front().unwrap().await??;
+                    }
+                    summary.files += 1;
+                }
+            }
+
+            while let Some(task) = tasks.pop_front() {
+                summary.bytes += task.await??;
+            }
+
+            for (local, metadata) in dirs.into_iter().rev() {
+                set_local_metadata(local, &metadata).await?;
+            }
+
+            Ok(summary)
+        }
+
+        inner(self, remote.as_ref(), local.as_ref()).await
+    }
+}
+
+pub(super) async fn download_file(
+    fs: Fs,
+    remote: PathBuf,
+    local: PathBuf,
+    metadata: Option<MetaData>,
+    progress: Option<Progress>,
+) -> Result<u64, Error> {
+    let mut file = OpenOptions::open_inner(
+        lowlevel::OpenOptions::new().read(true),
+        false,
+        false,
+        false,
+        &remote,
+        fs.write_end.clone(),
+    )
+    .await?;
+    file.set_progress(progress);
+
+    let mut local_file = local_fs::File::create(&local).await?;
+
+    let n = file
+        .read_all_into_pipelined(&mut local_file, default_num_requests())
+        .await?;
+
+    file.close().await?;
+    drop(local_file);
+
+    if let Some(metadata) = metadata {
+        set_local_metadata(local, &metadata).await?;
+    }
+
+    Ok(n)
+}
+
+pub(super) async fn upload_file(
+    fs: Fs,
+    local: PathBuf,
+    remote: PathBuf,
+    preserve: bool,
+    progress: Option<Progress>,
+) -> Result<u64, Error> {
+    let mut local_file = local_fs::File::open(&local).await?;
+
+    let remote = fs.concat_path_if_needed(&remote);
+    let mut file = OpenOptions::open_inner(
+        lowlevel::OpenOptions::new().write(true),
+        true,
+        true,
+        false,
+        &remote,
+        fs.write_end.clone(),
+    )
+    .await?;
+    file.set_progress(progress);
+
+    let n = file
+        .write_all_from(&mut local_file, default_num_requests())
+        .await?;
+
+    if preserve {
+        let metadata = local_file.metadata().await?;
+        file.set_metadata(to_metadata(&metadata)).await?;
+    }
+
+    file.close().await?;
+
+    Ok(n)
+}
+
+/// Return `path` of an entry at `depth` relative to `root`.
+///
+/// Every filename returned by the server must be exactly one normal
+/// component, otherwise joining it with the local destination
+/// could escape it.
+fn relative_path<'a>(root: &Path, path: &'a Path, depth: usize) -> Result<&'a Path, Error> {
+    let relative = path.strip_prefix(root).map_err(|_| invalid_filename())?;
+
+    let mut components = 0;
+    for component in relative.components() {
+        match component {
+            Component::Normal(_) => components += 1,
+            _ => return Err(invalid_filename()),
+        }
+    }
+
+    if components == depth {
+        Ok(relative)
+    } else {
+        Err(invalid_filename())
+    }
+}
+
+fn invalid_filename() -> Error {
+    Error::InvalidResponse(&"Filename of a dir entry is not a single normal component")
+}
+
+/// Create symlink `link` pointing to `target` on the remote.
+///
+/// Unlike [`Fs::symlink`], `target` is not joined with the cwd.
+async fn symlink(fs: &mut Fs, target: &Path, link: &Path) -> Result<(), Error> {
+    let link = fs.concat_path_if_needed(link);
+
+    fs.write_end
+        .send_request(|write_end, id| {
+            Ok(write_end
+                .send_symlink_request(id, Cow::Borrowed(target), link)?
+                .wait())
+        })
+        .await
+}
+
+/// Convert permissions and timestamps of local `metadata` to [`MetaData`].
+fn to_metadata(metadata: &Metadata) -> MetaData {
+    let mut builder = MetaDataBuilder::new();
+
+    if let Some(perm) = local_permissions(metadata) {
+        builder.permissions(perm);
+    }
+
+    if let Some(modified) = to_timestamp(metadata.modified()) {
+        let accessed = to_timestamp(metadata.accessed()).unwrap_or(modified);
+        builder.time(accessed, modified);
+    }
+
+    builder.create()
+}
+
+/// Return permissions of local `metadata`.
+#[cfg(unix)]
+fn local_permissions(metadata: &Metadata) -> Option<Permissions> {
+    Some(Permissions::from(
+        (metadata.permissions().mode() & 0o7777) as u16,
+    ))
+}
+
+/// Return permissions of local `metadata`.
+///
+/// Mode bits are only available on unix, elsewhere the server
+/// uses its default permissions.
+#[cfg(not(unix))]
+fn local_permissions(_metadata: &Metadata) -> Option<Permissions> {
+    None
+}
+
+/// Return [`lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS`] as [`NonZeroUsize`].
+fn default_num_requests() -> NonZeroUsize {
+    NonZeroUsize::new(lowlevel::OPENSSH_PORTABLE_DEFAULT_NUM_REQUESTS).unwrap()
+}
+
+fn to_timestamp(time: io::Result<SystemTime>) -> Option<UnixTimeStamp> {
+    time.ok().and_then(|time| UnixTimeStamp::new(time).ok())
+}
+
+/// Set permissions and timestamps of local `path` to `metadata`.
+async fn set_local_metadata(path: PathBuf, metadata: &MetaData) -> Result<(), Error> {
+    if let Some(perm) = metadata.permissions() {
+        set_local_permissions(&path, perm).await?;
+    }
+
+    let accessed = metadata
+        .accessed_precise()
+        .or_else(|| metadata.accessed().map(UnixTimeStamp::as_system_time));
+    let modified = metadata
+        .modified_precise()
+        .or_else(|| metadata.modified().map(UnixTimeStamp::as_system_time));
+
+    if accessed.is_some() || modified.is_some() {
+        tokio::task::spawn_blocking(move || set_local_times(&path, accessed, modified)).await??;
+    }
+
+    Ok(())
+}
+
+/// Set permissions of local `path` to `perm`.
+#[cfg(unix)]
+async fn set_local_permissions(path: &Path, perm: Permissions) -> io::Result<()> {
+    let perm = std::fs::Permissions::from_mode(u16::from(perm).into());
+    local_fs::set_permissions(path, perm).await
+}
+
+/// Set permissions of local `path` to `perm`.
+///
+/// Mode bits are only available on unix, elsewhere `path` is made
+/// read-only if `perm` does not allow anyone to write.
+#[cfg(not(unix))]
+async fn set_local_permissions(path: &Path, perm: Permissions) -> io::Result<()> {
+    let mut local_perm = local_fs::metadata(path).await?.permissions();
+    local_perm.set_readonly(u16::from(perm) & 0o222 == 0);
+    local_fs::set_permissions(path, local_perm).await
+}
+
+/// Create symlink `link` pointing to `target` on the local filesystem.
+#[cfg(unix)]
+async fn local_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    local_fs::symlink(target, link).await
+}
+
+/// Create symlink `link` pointing to `target` on the local filesystem.
+///
+/// Only supported on unix, since elsewhere the type of `target` has
+/// to be known to create the symlink.
+#[cfg(not(unix))]
+async fn local_symlink(_target: &Path, link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "Creating symlink {} is only supported on unix",
+            link.display()
+        ),
+    ))
+}
+
+/// Set accessed and modified time of local `path`, `None` leaves
+/// the time unchanged.
+fn set_local_times(
+    path: &Path,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+) -> io::Result<()> {
+    let accessed = accessed.map(FileTime::from_system_time);
+    let modified = modified.map(FileTime::from_system_time);
+
+    match (accessed, modified) {
+        (Some(accessed), Some(modified)) => filetime::set_file_times(path, accessed, modified),
+        (Some(accessed), None) => filetime::set_file_atime(path, accessed),
+        (None, Some(modified)) => filetime::set_file_mtime(path, modified),
+        (None, None) => Ok(()),
+    }
+}
+
+/// How to check the existing part of the destination before resuming
+/// a transfer with [`Fs::reput`] or [`Fs::reget`].
+///
+/// Regardless of the check, the destination is considered complete if
+/// it has the same size and mtime as the source, since the mtime is only
+/// set once the transfer is done.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ResumeCheck {
+    /// Resume from the end of the destination as long as it is not larger
+    /// than the source.
+    Size,
+
+    /// Like [`ResumeCheck::Size`], but the destination must also not be
+    /// modified before the source, otherwise the source might have changed
+    /// since the transfer was interrupted.
+    SizeAndMtime,
+
+    /// Like [`ResumeCheck::Size`], but the md5 of the last `n` bytes of the
+    /// destination must also match the same range of the source.
+    ///
+    /// The hash of the remote side is calculated by the server if it supports
+    /// md5 in the check-file or md5-hash-handle extension, otherwise the range
+    /// is read and hashed locally.
+    TailChecksum(u32),
+}
+
+/// Size and mtime of one side of a resumed transfer.
+#[derive(Debug, Copy, Clone)]
+struct Stat {
+    len: u64,
+    modified: Option<u32>,
+}
+
+impl Stat {
+    fn from_local(metadata: &Metadata) -> Self {
+        Self {
+            len: metadata.len(),
+            modified: to_timestamp(metadata.modified()).map(UnixTimeStamp::into_raw),
+        }
+    }
+
+    fn from_remote(metadata: &MetaData) -> Option<Self> {
+        Some(Self {
+            len: metadata.len()?,
+            modified: metadata.modified().map(UnixTimeStamp::into_raw),
+        })
+    }
+}
+
+/// Return offset to resume from, or `None` if `dst` is already complete.
+///
+/// The tail checksum is not checked here.
+fn resume_offset(check: ResumeCheck, src: Option<Stat>, dst: Option<Stat>) -> Option<u64> {
+    let (src, dst) = match (src, dst) {
+        (Some(src), Some(dst)) => (src, dst),
+        _ => return Some(0),
+    };
+
+    if dst.len == src.len && dst.modified.is_some() && dst.modified == src.modified {
+        None
+    } else if dst.len > src.len
+        || (check == ResumeCheck::SizeAndMtime && dst.modified < src.modified)
+    {
+        Some(0)
+    } else {
+        Some(dst.len)
+    }
+}
+
+/// Return md5 of `len` bytes of remote `file` starting at `offset`.
+async fn remote_tail_md5(
+    fs: &Fs,
+    file: &mut File,
+    offset: u64,
+    len: u64,
+) -> Result<Box<[u8]>, Error> {
+    if checksum_algorithms(&fs.write_end).contains(&"md5") {
+        match file.checksum("md5", offset..offset + len, 0).await {
+            Ok(checksum) => return Ok(checksum.hashes().into()),
+            // Only md5-hash is supported, which cannot be used with a handle.
+            Err(Error::UnsupportedExtension(_)) => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    file.seek(SeekFrom::Start(offset)).await?;
+
+    let mut hasher = Md5::new();
+    let mut buffer = BytesMut::new();
+    let mut remaining = len;
+
+    while remaining > 0 {
+        let n = min(remaining, TAIL_CHUNK_LEN as u64) as usize;
+
+        buffer.clear();
+        buffer = file.read_all(n, buffer).await?;
+        hasher.update(&buffer);
+
+        remaining -= n as u64;
+    }
+
+    Ok(hasher.finalize().to_vec().into())
+}
+
+/// Return md5 of `len` bytes of local `file` starting at `offset`.
+async fn local_tail_md5(
+    file: &mut local_fs::File,
+    offset: u64,
+    len: u64,
+) -> Result<Box<[u8]>, Error> {
+    file.seek(SeekFrom::Start(offset)).await?;
+
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0; min(len, TAIL_CHUNK_LEN as u64) as usize];
+    let mut remaining = len;
+
+    while remaining > 0 {
+        let n = min(remaining, buffer.len() as u64) as usize;
+
+        file.read_exact(&mut buffer[..n]).await?;
+        hasher.update(&buffer[..n]);
+
+        remaining -= n as u64;
+    }
+
+    Ok(hasher.finalize().to_vec().into())
+}
+
+/// Return `true` if the tail before `offset` is the same on both sides,
+/// or no tail checksum is requested.
+async fn check_tail(
+    check: ResumeCheck,
+    fs: &Fs,
+    file: &mut File,
+    local_file: &mut local_fs::File,
+    offset: u64,
+) -> Result<bool, Error> {
+    let len = match check {
+        ResumeCheck::TailChecksum(n) => min(u64::from(n), offset),
+        _ => return Ok(true),
+    };
+    if len == 0 {
+        return Ok(true);
+    }
+
+    let start = offset - len;
+
+    Ok(remote_tail_md5(fs, file, start, len).await?
+        == local_tail_md5(local_file, start, len).await?)
+}
+
+/// Upload `local` to `remote`, resuming from the end of `remote` if it
+/// passes `check`.
+pub(super) async fn reput(
+    fs: &Fs,
+    local: &Path,
+    remote: &Path,
+    check: ResumeCheck,
+) -> Result<u64, Error> {
+    let mut local_file = local_fs::File::open(local).await?;
+    let local_metadata = local_file.metadata().await?;
+
+    let remote = fs.concat_path_if_needed(remote);
+    let mut file = OpenOptions::open_inner(
+        lowlevel::OpenOptions::new().read(true).write(true),
+        false,
+        true,
+        false,
+        &remote,
+        fs.write_end.clone(),
+    )
+    .await?;
+
+    // A missing `remote` is created empty, so it is resumed from `0`.
+    let src = Stat::from_local(&local_metadata);
+    let dst = Stat::from_remote(&file.metadata().await?);
+
+    let mut offset = match resume_offset(check, Some(src), dst) {
+        Some(offset) => offset,
+        None => return file.close().await.map(|()| 0),
+    };
+    if !check_tail(check, fs, &mut file, &mut local_file, offset).await? {
+        offset = 0;
+    }
+
+    if dst.map(|dst| dst.len) != Some(offset) {
+        file.set_len(offset).await?;
+    }
+    file.seek(SeekFrom::Start(offset)).await?;
+    local_file.seek(SeekFrom::Start(offset)).await?;
+
+    let n = file
+        .write_all_from(&mut local_file, default_num_requests())
+        .await?;
+
+    // Setting mtime marks the file as complete.
+    if let Some(modified) = to_timestamp(local_metadata.modified()) {
+        let accessed = to_timestamp(local_metadata.accessed()).unwrap_or(modified);
+        file.set_metadata(MetaDataBuilder::new().time(accessed, modified).create())
+            .await?;
+    }
+
+    file.close().await?;
+
+    Ok(n)
+}
+
+/// Download `remote` to `local`, resuming from the end of `local` if it
+/// passes `check`.
+pub(super) async fn reget(
+    fs: &Fs,
+    remote: &Path,
+    local: &Path,
+    check: ResumeCheck,
+) -> Result<u64, Error> {
+    let remote = fs.concat_path_if_needed(remote);
+    let mut file = OpenOptions::open_inner(
+        lowlevel::OpenOptions::new().read(true),
+        false,
+        false,
+        false,
+        &remote,
+        fs.write_end.clone(),
+    )
+    .await?;
+    let metadata = file.metadata().await?;
+
+    let dst = match local_fs::metadata(local).await {
+        Ok(metadata) => Some(Stat::from_local(&metadata)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut offset = match resume_offset(check, Stat::from_remote(&metadata), dst) {
+        Some(offset) => offset,
+        None => return file.close().await.map(|()| 0),
+    };
+
+    let mut local_file = local_fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(local)
+        .await?;
+
+    if !check_tail(check, fs, &mut file, &mut local_file, offset).await? {
+        offset = 0;
+    }
+
+    if dst.map(|dst| dst.len) != Some(offset) {
+        local_file.set_len(offset).await?;
+    }
+    file.seek(SeekFrom::Start(offset)).await?;
+    local_file.seek(SeekFrom::Start(offset)).await?;
+
+    let n = file
+        .read_all_into_pipelined(&mut local_file, default_num_requests())
+        .await?;
+
+    file.close().await?;
+    drop(local_file);
+
+    // Setting mtime marks the file as complete.
+    if let Some(modified) = metadata.modified() {
+        let accessed = metadata.accessed().unwrap_or(modified);
+        let metadata = MetaDataBuilder::new().time(accessed, modified).create();
+        set_local_metadata(local.to_owned(), &metadata).await?;
+    }
+
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(len: u64, modified: u32) -> Option<Stat> {
+        Some(Stat {
+            len,
+            modified: Some(modified),
+        })
+    }
+
+    #[test]
+    fn test_resume_offset() {
+        use ResumeCheck::*;
+
+        assert_eq!(resume_offset(Size, stat(10, 5), None), Some(0));
+        assert_eq!(resume_offset(Size, stat(10, 5), stat(10, 5)), None);
+        assert_eq!(resume_offset(Size, stat(10, 5), stat(10, 6)), Some(10));
+        assert_eq!(resume_offset(Size, stat(10, 5), stat(4, 4)), Some(4));
+        assert_eq!(resume_offset(Size, stat(10, 5), stat(12, 6)), Some(0));
+
+        assert_eq!(
+            resume_offset(SizeAndMtime, stat(10, 5), stat(4, 4)),
+            Some(0)
+        );
+        assert_eq!(
+            resume_offset(SizeAndMtime, stat(10, 5), stat(4, 6)),
+            Some(4)
+        );
+        assert_eq!(resume_offset(SizeAndMtime, stat(10, 5), stat(10, 5)), None);
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let root = Path::new("/root");
+
+        assert_eq!(relative_path(root, root, 0).unwrap(), Path::new(""));
+        assert_eq!(
+            relative_path(root, Path::new("/root/a/b"), 2).unwrap(),
+            Path::new("a/b")
+        );
+
+        // Filenames containing `/`, `..` or empty.
+        assert!(relative_path(root, Path::new("/root/a/b"), 1).is_err());
+        assert!(relative_path(root, Path::new("/root/a/.."), 2).is_err());
+        assert!(relative_path(root, Path::new("/root"), 1).is_err());
+
+        // Absolute filename replacing the root.
+        assert!(relative_path(root, Path::new("/etc"), 1).is_err());
+    }
+}
@@ -0,0 +1,597 @@
+use crate::{
+    lowlevel,
+    metadata::{FileType, MetaData},
+    Error,
+};
+
+use super::Fs;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::OsStr,
+    fmt,
+    future::{poll_fn, Future},
+    io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::{FusedStream, Stream};
+
+/// Default value of [`WalkDir::max_concurrent_dirs`].
+const DEFAULT_MAX_CONCURRENT_DIRS: usize = 8;
+
+type Filter = Box<dyn FnMut(&WalkDirEntry) -> bool + Send>;
+type ReadFuture = Pin<Box<dyn Future<Output = Result<Vec<ReadEntry>, Error>> + Send>>;
+
+/// Entries returned by the [`WalkDirStream`].
+#[derive(Debug, Clone)]
+pub struct WalkDirEntry {
+    path: PathBuf,
+    depth: usize,
+    metadata: MetaData,
+    path_is_symlink: bool,
+}
+
+impl WalkDirEntry {
+    /// Return the full path of the entry, which is the root passed to
+    /// [`Fs::walk_dir`] joined with the filename of the entry and
+    /// all its parents.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Convert the entry into its full path.
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    /// Return the filename of the entry.
+    ///
+    /// If the entry is the root and it has no filename (e.g. `/`),
+    /// then the whole path is returned.
+    pub fn file_name(&self) -> &OsStr {
+        self.path
+            .file_name()
+            .unwrap_or_else(|| self.path.as_os_str())
+    }
+
+    /// Return the depth of the entry, the root is at depth `0`.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Return metadata for the entry.
+    ///
+    /// If the entry is a symlink followed by the walk, this is the
+    /// metadata of its target.
+    pub fn metadata(&self) -> &MetaData {
+        &self.metadata
+    }
+
+    /// Return the file type for the entry.
+    pub fn file_type(&self) -> Option<FileType> {
+        self.metadata.file_type()
+    }
+
+    /// Return `true` if the entry is a symlink, regardless of whether
+    /// it is followed.
+    pub fn path_is_symlink(&self) -> bool {
+        self.path_is_symlink
+    }
+
+    fn is_dir(&self) -> bool {
+        self.file_type().map(|t| t.is_dir()).unwrap_or(false)
+    }
+}
+
+/// Builder of [`WalkDirStream`], created by [`Fs::walk_dir`].
+#[must_use]
+pub struct WalkDir {
+    fs: Fs,
+    root: PathBuf,
+    max_depth: usize,
+    follow_links: bool,
+    contents_first: bool,
+    max_concurrent_dirs: NonZeroUsize,
+    filter: Option<Filter>,
+}
+
+impl fmt::Debug for WalkDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalkDir")
+            .field("root", &self.root)
+            .field("max_depth", &self.max_depth)
+            .field("follow_links", &self.follow_links)
+            .field("contents_first", &self.contents_first)
+            .field("max_concurrent_dirs", &self.max_concurrent_dirs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WalkDir {
+    pub(super) fn new(mut fs: Fs, root: PathBuf) -> Self {
+        // `root` is already joined with the cwd, don't do it
+        // again for the requests sent by the walk.
+        fs.set_cwd("");
+
+        Self {
+            fs,
+            root,
+            max_depth: lowlevel::OPENSSH_PORTABLE_MAX_DIR_DEPTH,
+            follow_links: false,
+            contents_first: false,
+            max_concurrent_dirs: NonZeroUsize::new(DEFAULT_MAX_CONCURRENT_DIRS).unwrap(),
+            filter: None,
+        }
+    }
+
+    /// Set the maximum depth of entries yielded, the root is at depth `0`.
+    ///
+    /// Directories at the maximum depth are yielded, but not descended into.
+    ///
+    /// Default to [`lowlevel::OPENSSH_PORTABLE_MAX_DIR_DEPTH`].
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Follow symlinks if set to `true`.
+    ///
+    /// Symlinks that are followed are yielded with the metadata of
+    /// their target, and descended into if the target is a directory.
+    /// Dangling symlinks are yielded with the metadata of the symlink itself.
+    ///
+    /// If a symlink points to one of its ancestors, then an error is
+    /// yielded in place of the symlink to break the loop.
+    ///
+    /// Default to `false`.
+    pub fn follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Yield the contents of a directory before the directory itself
+    /// (post-order) if set to `true`.
+    ///
+    /// Default to `false`, which yields the directory first (pre-order).
+    pub fn contents_first(mut self, contents_first: bool) -> Self {
+        self.contents_first = contents_first;
+        self
+    }
+
+    /// Set the maximum number of directories read at the same time.
+    ///
+    /// Directories are read ahead of the walk, in the order they
+    /// would be yielded, so that their requests are pipelined.
+    ///
+    /// Each of them takes up an open handle while it is being read,
+    /// which counts towards
+    /// [`Sftp::max_open_handles`](crate::sftp::Sftp::max_open_handles).
+    ///
+    /// Default to `8`.
+    pub fn max_concurrent_dirs(mut self, max_concurrent_dirs: NonZeroUsize) -> Self {
+        self.max_concurrent_dirs = max_concurrent_dirs;
+        self
+    }
+
+    /// Only yield entries for which `predicate` returns `true`.
+    ///
+    /// If `predicate` returns `false` for a directory, then it is not
+    /// descended into, pruning the whole subtree.
+    pub fn filter_entry<P>(mut self, predicate: P) -> Self
+    where
+        P: FnMut(&WalkDirEntry) -> bool + Send + 'static,
+    {
+        self.filter = Some(Box::new(predicate));
+        self
+    }
+
+    /// Start walking the tree.
+    pub fn into_stream(self) -> WalkDirStream {
+        let root_read = Box::pin(read_root(
+            self.fs.clone(),
+            self.root.clone(),
+            self.follow_links,
+        ));
+
+        let mut reads = Reads::default();
+        reads.reads.insert(0, Read::Pending(root_read));
+        reads.next_id = 1;
+        reads.pending = 1;
+
+        WalkDirStream {
+            // The root is the only entry of a pseudo directory
+            // at the bottom of the stack.
+            stack: vec![Frame {
+                dir: None,
+                canonical: None,
+                read: Some(0),
+                entries: VecDeque::new(),
+                cursor: 0,
+            }],
+            reads,
+            walk_dir: self,
+        }
+    }
+}
+
+/// Entry read from a directory before it is converted to [`Child`].
+struct ReadEntry {
+    entry: WalkDirEntry,
+    /// Canonical path of symlinks that point to a directory,
+    /// used to detect loops.
+    canonical: Option<PathBuf>,
+}
+
+enum Read {
+    Pending(ReadFuture),
+    Ready(Result<Vec<ReadEntry>, Error>),
+}
+
+enum Child {
+    Entry {
+        entry: WalkDirEntry,
+        /// `Some` if the walk descends into the entry.
+        descend: Option<Descend>,
+    },
+    Error(Error),
+}
+
+struct Descend {
+    canonical: Option<PathBuf>,
+    /// Id of the read in [`WalkDirStream::reads`], `None` if not started yet.
+    read: Option<u64>,
+}
+
+/// A directory being walked.
+struct Frame {
+    /// The directory itself, only stored if it is yielded after its contents.
+    dir: Option<WalkDirEntry>,
+    /// Canonical path of the directory, only available if following symlinks.
+    canonical: Option<PathBuf>,
+    /// Id of the read of the directory, `None` once the result is taken.
+    read: Option<u64>,
+    entries: VecDeque<Child>,
+    /// Index of the first entry in `entries` whose read is not started yet.
+    cursor: usize,
+}
+
+/// Stream of entries of a remote tree, created by [`WalkDir::into_stream`].
+///
+/// Any error encountered when reading a directory is yielded in place of
+/// its contents, and the walk continues with the rest of the tree.
+pub struct WalkDirStream {
+    walk_dir: WalkDir,
+    /// Stack of directories from the root to the current one.
+    stack: Vec<Frame>,
+    reads: Reads,
+}
+
+#[derive(Default)]
+struct Reads {
+    reads: HashMap<u64, Read>,
+    next_id: u64,
+    /// Number of reads in `reads` that are not ready yet.
+    pending: usize,
+}
+
+impl fmt::Debug for WalkDirStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalkDirStream")
+            .field("walk_dir", &self.walk_dir)
+            .field("depth", &self.stack.len())
+            .field("pending_reads", &self.reads.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Reads {
+    fn start(&mut self, walk_dir: &WalkDir, path: PathBuf, depth: usize) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let future = read_dir(walk_dir.fs.clone(), path, depth, walk_dir.follow_links);
+        self.reads.insert(id, Read::Pending(Box::pin(future)));
+        self.pending += 1;
+
+        id
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) {
+        for read in self.reads.values_mut() {
+            if let Read::Pending(future) = read {
+                if let Poll::Ready(res) = future.as_mut().poll(cx) {
+                    *read = Read::Ready(res);
+                    self.pending -= 1;
+                }
+            }
+        }
+    }
+}
+
+impl WalkDirStream {
+    /// Start reading directories in the order they would be yielded,
+    /// until `max_concurrent_dirs` is reached.
+    fn start_reads(&mut self) {
+        let Self {
+            walk_dir,
+            stack,
+            reads,
+        } = self;
+        let max = walk_dir.max_concurrent_dirs.get();
+
+        for frame in stack.iter_mut().rev() {
+            while reads.pending < max {
+                let child = match frame.entries.get_mut(frame.cursor) {
+                    Some(child) => child,
+                    None => break,
+                };
+                frame.cursor += 1;
+
+                if let Child::Entry {
+                    entry,
+                    descend: Some(descend),
+                } = child
+                {
+                    if descend.read.is_none() {
+                        let id = reads.start(walk_dir, entry.path.clone(), entry.depth + 1);
+                        descend.read = Some(id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Convert entries read from the directory at the top of the stack
+    /// to [`Child`].
+    fn make_children(&mut self, entries: Vec<ReadEntry>) -> VecDeque<Child> {
+        let walk_dir = &mut self.walk_dir;
+        let stack = &self.stack;
+        let parent_canonical = stack.last().and_then(|frame| frame.canonical.as_deref());
+
+        entries
+            .into_iter()
+            .filter(|read_entry| match &mut walk_dir.filter {
+                Some(filter) => filter(&read_entry.entry),
+                None => true,
+            })
+            .map(|ReadEntry { entry, canonical }| {
+                if !entry.is_dir() || entry.depth >= walk_dir.max_depth {
+                    return Child::Entry {
+                        entry,
+                        descend: None,
+                    };
+                }
+
+                let canonical = if walk_dir.follow_links {
+                    canonical
+                        .or_else(|| parent_canonical.map(|parent| parent.join(entry.file_name())))
+                } else {
+                    None
+                };
+
+                if let Some(canonical) = &canonical {
+                    let is_loop = stack
+                        .iter()
+                        .any(|frame| frame.canonical.as_ref() == Some(canonical));
+
+                    if is_loop {
+                        return Child::Error(loop_error(&entry.path, canonical).into());
+                    }
+                }
+
+                Child::Entry {
+                    entry,
+                    descend: Some(Descend {
+                        canonical,
+                        read: None,
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    fn poll_next_entry(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<WalkDirEntry, Error>>> {
+        loop {
+            self.start_reads();
+            self.reads.poll(cx);
+
+            let frame = match self.stack.last_mut() {
+                Some(frame) => frame,
+                None => return Poll::Ready(None),
+            };
+
+            if let Some(id) = frame.read {
+                match self.reads.reads.remove(&id) {
+                    Some(Read::Ready(res)) => {
+                        frame.read = None;
+
+                        match res {
+                            Ok(entries) => {
+                                let children = self.make_children(entries);
+                                self.stack.last_mut().unwrap().entries = children;
+                            }
+                            Err(err) => return Poll::Ready(Some(Err(err))),
+                        }
+                    }
+                    Some(read @ Read::Pending(_)) => {
+                        self.reads.reads.insert(id, read);
+                        return Poll::Pending;
+                    }
+                    None => unreachable!("Read of the directory is missing"),
+                }
+                continue;
+            }
+
+            let child = match frame.entries.pop_front() {
+                Some(child) => child,
+                None => {
+                    let frame = self.stack.pop().unwrap();
+                    match frame.dir {
+                        Some(dir) => return Poll::Ready(Some(Ok(dir))),
+                        None => continue,
+                    }
+                }
+            };
+            frame.cursor = frame.cursor.saturating_sub(1);
+
+            let (entry, descend) = match child {
+                Child::Entry { entry, descend } => (entry, descend),
+                Child::Error(err) => return Poll::Ready(Some(Err(err))),
+            };
+
+            let descend = match descend {
+                Some(descend) => descend,
+                None => return Poll::Ready(Some(Ok(entry))),
+            };
+
+            let read = match descend.read {
+                Some(id) => id,
+                None => self
+                    .reads
+                    .start(&self.walk_dir, entry.path.clone(), entry.depth + 1),
+            };
+
+            let contents_first = self.walk_dir.contents_first;
+            let (dir, ret) = if contents_first {
+                (Some(entry), None)
+            } else {
+                (None, Some(entry))
+            };
+
+            self.stack.push(Frame {
+                dir,
+                canonical: descend.canonical,
+                read: Some(read),
+                entries: VecDeque::new(),
+                cursor: 0,
+            });
+
+            if let Some(entry) = ret {
+                return Poll::Ready(Some(Ok(entry)));
+            }
+        }
+    }
+}
+
+impl Stream for WalkDirStream {
+    type Item = Result<WalkDirEntry, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_entry(cx)
+    }
+}
+
+impl FusedStream for WalkDirStream {
+    fn is_terminated(&self) -> bool {
+        self.stack.is_empty()
+    }
+}
+
+/// Get the entry of the root.
+async fn read_root(mut fs: Fs, root: PathBuf, follow_links: bool) -> Result<Vec<ReadEntry>, Error> {
+    let symlink_metadata = fs.symlink_metadata(&root).await?;
+    let path_is_symlink = is_symlink(&symlink_metadata);
+
+    let entry = WalkDirEntry {
+        path: root,
+        depth: 0,
+        metadata: symlink_metadata,
+        path_is_symlink,
+    };
+
+    Ok(vec![resolve(&mut fs, entry, follow_links, true).await])
+}
+
+/// Read all entries of the directory at `path`.
+async fn read_dir(
+    mut fs: Fs,
+    path: PathBuf,
+    depth: usize,
+    follow_links: bool,
+) -> Result<Vec<ReadEntry>, Error> {
+    let read_dir = fs.open_dir(&path).await?.read_dir();
+    tokio::pin!(read_dir);
+
+    let mut entries = Vec::new();
+
+    while let Some(dir_entry) = poll_fn(|cx| read_dir.as_mut().poll_next(cx)).await {
+        let dir_entry = dir_entry?;
+
+        let filename = dir_entry.filename();
+        if filename == Path::new(".") || filename == Path::new("..") {
+            continue;
+        }
+
+        let metadata = dir_entry.metadata();
+        let path_is_symlink = is_symlink(&metadata);
+
+        let entry = WalkDirEntry {
+            path: path.join(filename),
+            depth,
+            metadata,
+            path_is_symlink,
+        };
+
+        entries.push(resolve(&mut fs, entry, follow_links, false).await);
+    }
+
+    Ok(entries)
+}
+
+/// Follow `entry` if it is a symlink and `follow_links` is `true`.
+///
+/// * `is_root` - the canonical path of the root is needed to detect loops
+///   even if it is not a symlink.
+async fn resolve(
+    fs: &mut Fs,
+    mut entry: WalkDirEntry,
+    follow_links: bool,
+    is_root: bool,
+) -> ReadEntry {
+    if !follow_links || !(entry.path_is_symlink || is_root) {
+        return ReadEntry {
+            entry,
+            canonical: None,
+        };
+    }
+
+    if entry.path_is_symlink {
+        // Dangling symlinks are yielded as is.
+        if let Ok(metadata) = fs.metadata(&entry.path).await {
+            entry.metadata = metadata;
+        }
+    }
+
+    let canonical = if entry.is_dir() {
+        fs.canonicalize(&entry.path).await.ok()
+    } else {
+        None
+    };
+
+    ReadEntry { entry, canonical }
+}
+
+fn is_symlink(metadata: &MetaData) -> bool {
+    metadata
+        .file_type()
+        .map(|t| t.is_symlink())
+        .unwrap_or(false)
+}
+
+pub(super) fn loop_error(path: &Path, ancestor: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!(
+            "File system loop found: {} points to an ancestor {}",
+            path.display(),
+            ancestor.display()
+        ),
+    )
+}
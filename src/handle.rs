@@ -11,12 +11,16 @@ use std::{
 };
 
 use derive_destructure2::destructure;
+use tokio::sync::OwnedSemaphorePermit;
 
 /// Remote Directory
 #[derive(Debug, Clone, destructure)]
 pub(super) struct OwnedHandle {
     pub(super) write_end: WriteEndWithCachedId,
     pub(super) handle: Arc<HandleOwned>,
+    /// Slot of the handle in `Auxiliary::handle_semaphore`, released
+    /// once the handle is closed.
+    permit: Arc<OwnedSemaphorePermit>,
 }
 
 impl Drop for OwnedHandle {
@@ -26,6 +30,7 @@ impl Drop for OwnedHandle {
 
         if Arc::strong_count(handle) == 1 {
             // This is the last reference to the arc
+            let permit = Arc::clone(&self.permit);
             let id = write_end.get_id_mut();
             match write_end.send_close_request(id, Cow::Borrowed(handle)) {
                 Ok(response) => {
@@ -42,6 +47,7 @@ impl Drop for OwnedHandle {
                     let future = response.wait();
                     self.get_auxiliary().tokio_handle().spawn(async move {
                         let _res = future.await;
+                        drop(permit);
                         #[cfg(feature = "tracing")]
                         match _res {
                             Ok(_) => tracing::debug!("close handle success"),
@@ -59,10 +65,38 @@ impl Drop for OwnedHandle {
 }
 
 impl OwnedHandle {
-    pub(super) fn new(write_end: WriteEndWithCachedId, handle: HandleOwned) -> Self {
+    /// Wait for a free slot in `Auxiliary::handle_semaphore`, which must be
+    /// acquired before opening a new handle.
+    ///
+    /// # Cancel Safety
+    ///
+    /// This function is cancel safe.
+    pub(super) async fn acquire_permit(
+        write_end: &mut WriteEndWithCachedId,
+    ) -> Result<OwnedSemaphorePermit, Error> {
+        let semaphore = Arc::clone(write_end.get_auxiliary().handle_semaphore());
+
+        write_end
+            .cancel_if_task_failed(async move {
+                Ok::<_, Error>(
+                    semaphore
+                        .acquire_owned()
+                        .await
+                        .expect("handle_semaphore is never closed"),
+                )
+            })
+            .await
+    }
+
+    pub(super) fn new(
+        write_end: WriteEndWithCachedId,
+        handle: HandleOwned,
+        permit: OwnedSemaphorePermit,
+    ) -> Self {
         Self {
             write_end,
             handle: Arc::new(handle),
+            permit: Arc::new(permit),
         }
     }
 
@@ -79,6 +113,22 @@ impl OwnedHandle {
             .await
     }
 
+    /// Same as [`OwnedHandle::send_request`], except that the request is sent
+    /// using a clone of `self.write_end`, so that the handle can be shared.
+    pub(super) async fn send_request_shared<Func, F, R>(&self, f: Func) -> Result<R, Error>
+    where
+        Func: FnOnce(&mut WriteEnd, Cow<'_, Handle>, Id) -> Result<F, Error> + Send,
+        F: Future<Output = Result<(Id, R), Error>> + Send + 'static,
+        R: Send,
+    {
+        let handle = &self.handle;
+
+        self.write_end
+            .clone()
+            .send_request(|write_end, id| f(write_end, Cow::Borrowed(handle), id))
+            .await
+    }
+
     /// Close the [`OwnedHandle`], send the close request
     /// if this is the last reference.
     ///
@@ -90,15 +140,19 @@ impl OwnedHandle {
             // This is the last reference to the arc
 
             // Release resources without running `Drop::drop`
-            let (mut write_end, handle) = self.destructure();
+            let (mut write_end, handle, permit) = self.destructure();
 
-            write_end
+            let res = write_end
                 .send_request(|write_end, id| {
                     Ok(write_end
                         .send_close_request(id, Cow::Borrowed(&handle))?
                         .wait())
                 })
-                .await
+                .await;
+
+            drop(permit);
+
+            res
         } else {
             Ok(())
         }
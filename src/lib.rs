@@ -31,6 +31,13 @@
 //!  - hardlink
 //!  - posix rename
 //!  - copy data
+//!  - statvfs
+//!  - fstatvfs
+//!  - lsetstat
+//!  - users-groups-by-id
+//!  - home-directory
+//!  - check-file
+//!  - md5-hash
 //!
 //! [openssh]: https://crates.io/crates/openssh
 //! [sftp v3]: https://www.openssh.com/txt/draft-ietf-secsh-filexfer-02.txt
@@ -60,6 +67,9 @@ use bytes::BytesMut;
 mod unix_timestamp;
 pub use unix_timestamp::UnixTimeStamp;
 
+mod progress;
+pub use progress::{Progress, ProgressObserver, TransferProgress};
+
 mod sftp;
 use sftp::SftpHandle;
 #[cfg(feature = "openssh")]
@@ -77,6 +87,8 @@ use queue::MpscQueue;
 
 mod tasks;
 
+mod rate_limit;
+
 mod auxiliary;
 use auxiliary::Auxiliary;
 
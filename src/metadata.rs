@@ -1,8 +1,10 @@
 use super::{
-    lowlevel::{FileAttrs, FileType as SftpFileType, Permissions as SftpPermissions},
+    lowlevel::{Attrs, FileAttrs, FileType as SftpFileType, Permissions as SftpPermissions},
     UnixTimeStamp,
 };
 
+use std::time::SystemTime;
+
 /// Builder of [`MetaData`].
 #[derive(Debug, Default, Copy, Clone)]
 pub struct MetaDataBuilder(FileAttrs);
@@ -20,6 +22,9 @@ impl MetaDataBuilder {
     }
 
     /// Set id of the metadata to be built.
+    ///
+    /// Sending it fails if the negotiated sftp version is 4 or later,
+    /// since the server takes owner and group names instead.
     pub fn id(&mut self, (uid, gid): (u32, u32)) -> &mut Self {
         self.0.set_id(uid, gid);
         self
@@ -50,18 +55,25 @@ impl MetaDataBuilder {
 }
 
 /// Metadata information about a file.
+///
+/// Attributes only available in sftp v4 and later are `None` if
+/// the negotiated sftp version is 3.
 #[repr(transparent)]
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub struct MetaData(FileAttrs);
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MetaData(Attrs);
 
 #[allow(clippy::len_without_is_empty)]
 impl MetaData {
     pub(super) fn new(attrs: FileAttrs) -> Self {
+        Self(attrs.into())
+    }
+
+    pub(super) fn from_attrs(attrs: Attrs) -> Self {
         Self(attrs)
     }
 
     pub(super) fn into_inner(self) -> FileAttrs {
-        self.0
+        self.0.into_inner()
     }
 
     /// Returns the size of the file in bytes.
@@ -75,7 +87,8 @@ impl MetaData {
     /// Returns the user ID of the owner.
     ///
     /// Return `None` if the server did not return
-    /// the uid.
+    /// the uid, or if the negotiated sftp version is 4 or later
+    /// and the server returned a name, see [`MetaData::owner`].
     pub fn uid(&self) -> Option<u32> {
         self.0.get_id().map(|(uid, _gid)| uid)
     }
@@ -83,7 +96,8 @@ impl MetaData {
     /// Returns the group ID of the owner.
     ///
     /// Return `None` if the server did not return
-    /// the gid.
+    /// the gid, or if the negotiated sftp version is 4 or later
+    /// and the server returned a name, see [`MetaData::group`].
     pub fn gid(&self) -> Option<u32> {
         self.0.get_id().map(|(_uid, gid)| gid)
     }
@@ -125,6 +139,99 @@ impl MetaData {
             .map(|(_atime, mtime)| mtime)
             .map(UnixTimeStamp)
     }
+
+    /// Returns the owner of the file, usually in the form of `user@domain`.
+    ///
+    /// Return `None` if the negotiated sftp version is 3 or
+    /// the server did not return the owner.
+    pub fn owner(&self) -> Option<&str> {
+        self.0.ext.as_ref()?.owner.as_deref()
+    }
+
+    /// Returns the group of the file, usually in the form of `group@domain`.
+    ///
+    /// Return `None` if the negotiated sftp version is 3 or
+    /// the server did not return the group.
+    pub fn group(&self) -> Option<&str> {
+        self.0.ext.as_ref()?.group.as_deref()
+    }
+
+    /// Returns the last access time with sub-second precision.
+    ///
+    /// Return `None` if the negotiated sftp version is 3,
+    /// the server did not return the last access time or
+    /// [`SystemTime`] cannot hold it.
+    pub fn accessed_precise(&self) -> Option<SystemTime> {
+        self.0.ext.as_ref()?.accessed?.as_system_time()
+    }
+
+    /// Returns the last modification time with sub-second precision.
+    ///
+    /// Return `None` if the negotiated sftp version is 3,
+    /// the server did not return the last modification time or
+    /// [`SystemTime`] cannot hold it.
+    pub fn modified_precise(&self) -> Option<SystemTime> {
+        self.0.ext.as_ref()?.modified?.as_system_time()
+    }
+
+    /// Returns the creation time.
+    ///
+    /// Return `None` if the negotiated sftp version is 3,
+    /// the server did not return the creation time or
+    /// [`SystemTime`] cannot hold it.
+    pub fn created(&self) -> Option<SystemTime> {
+        self.0.ext.as_ref()?.created?.as_system_time()
+    }
+
+    /// Returns the last time the attributes were changed.
+    ///
+    /// Return `None` if the negotiated sftp version is less than 6,
+    /// the server did not return the time or
+    /// [`SystemTime`] cannot hold it.
+    pub fn changed(&self) -> Option<SystemTime> {
+        self.0.ext.as_ref()?.changed?.as_system_time()
+    }
+
+    /// Returns the raw access control list, which consists of
+    /// the ace count followed by the aces.
+    ///
+    /// Return `None` if the negotiated sftp version is 3 or
+    /// the server did not return the acl.
+    pub fn acl(&self) -> Option<&[u8]> {
+        self.0.ext.as_ref()?.acl.as_deref()
+    }
+
+    /// Returns the raw `SSH_FILEXFER_ATTR_FLAGS_*` bits.
+    ///
+    /// Return `None` if the negotiated sftp version is less than 5 or
+    /// the server did not return them.
+    pub fn attrib_bits(&self) -> Option<u32> {
+        self.0.ext.as_ref()?.attrib_bits
+    }
+
+    /// Returns the number of bytes allocated on disk.
+    ///
+    /// Return `None` if the negotiated sftp version is less than 6 or
+    /// the server did not return it.
+    pub fn allocation_size(&self) -> Option<u64> {
+        self.0.ext.as_ref()?.allocation_size
+    }
+
+    /// Returns the mime type of the file.
+    ///
+    /// Return `None` if the negotiated sftp version is less than 6 or
+    /// the server did not return it.
+    pub fn mime_type(&self) -> Option<&str> {
+        self.0.ext.as_ref()?.mime_type.as_deref()
+    }
+
+    /// Returns the number of hard links.
+    ///
+    /// Return `None` if the negotiated sftp version is less than 6 or
+    /// the server did not return it.
+    pub fn link_count(&self) -> Option<u32> {
+        self.0.ext.as_ref()?.link_count
+    }
 }
 
 /// A structure representing a type of file with accessors for each file type.
@@ -337,3 +444,12 @@ impl From<u16> for Permissions {
         result
     }
 }
+
+impl From<Permissions> for u16 {
+    /// Converts a [`Permissions`] object into numeric file mode bits,
+    /// the reverse of `From<u16> for Permissions`.
+    fn from(perm: Permissions) -> Self {
+        // All bits of the permissions fit in 12 bits.
+        perm.0.bits() as u16
+    }
+}
@@ -1,5 +1,7 @@
+use super::lowlevel;
+
 use std::{
-    num::{NonZeroU16, NonZeroUsize},
+    num::{NonZeroU16, NonZeroU64, NonZeroUsize},
     time::Duration,
 };
 
@@ -14,6 +16,11 @@ pub struct SftpOptions {
     flush_interval: Option<Duration>,
     max_pending_requests: Option<NonZeroU16>,
     tokio_compat_file_write_limit: Option<NonZeroUsize>,
+    cache_names: bool,
+    max_sftp_version: Option<u32>,
+    max_open_handles: Option<NonZeroUsize>,
+    upload_rate_limit: Option<NonZeroU64>,
+    download_rate_limit: Option<NonZeroU64>,
 
     #[cfg(feature = "__ci-tests")]
     max_read_len: Option<NonZeroU32>,
@@ -30,6 +37,11 @@ impl SftpOptions {
             flush_interval: None,
             max_pending_requests: None,
             tokio_compat_file_write_limit: None,
+            cache_names: false,
+            max_sftp_version: None,
+            max_open_handles: None,
+            upload_rate_limit: None,
+            download_rate_limit: None,
 
             #[cfg(feature = "__ci-tests")]
             max_read_len: None,
@@ -138,6 +150,100 @@ impl SftpOptions {
             .map(NonZeroUsize::get)
             .unwrap_or(640 * 1024)
     }
+
+    /// Set whether names resolved by [`crate::fs::Fs::lookup_names`]
+    /// are cached.
+    ///
+    /// If enabled, each uid/gid is only sent to the server once
+    /// for the lifetime of [`super::Sftp`], which avoids repeated
+    /// lookups when listing many directories owned by the same users.
+    ///
+    /// It is disabled by default.
+    #[must_use]
+    pub const fn cache_names(mut self, cache_names: bool) -> Self {
+        self.cache_names = cache_names;
+        self
+    }
+
+    pub(super) fn get_cache_names(&self) -> bool {
+        self.cache_names
+    }
+
+    /// Set the highest sftp protocol version to negotiate with the server,
+    /// default value is 3.
+    ///
+    /// The version actually used is the lower one of `max_sftp_version`
+    /// and the version the server supports, which can be obtained via
+    /// [`super::Sftp::sftp_version`].
+    ///
+    /// Only version 3 to 6 is supported, other values would cause
+    /// [`super::Sftp::new`] to fail with
+    /// [`super::Error::UnsupportedSftpProtocol`].
+    #[must_use]
+    pub const fn max_sftp_version(mut self, max_sftp_version: u32) -> Self {
+        self.max_sftp_version = Some(max_sftp_version);
+        self
+    }
+
+    pub(super) fn get_max_sftp_version(&self) -> u32 {
+        self.max_sftp_version.unwrap_or(lowlevel::SFTP_MIN_VERSION)
+    }
+
+    /// Set `max_open_handles`, which overrides the maximum number of
+    /// open handles returned by the server via the limits extension.
+    ///
+    /// Once the limit is reached, opening a new file or directory
+    /// waits until another one is closed.
+    ///
+    /// By default, the limit returned by the server is used, and if the
+    /// server does not return one, the number of open handles is unlimited.
+    #[must_use]
+    pub const fn max_open_handles(mut self, max_open_handles: NonZeroUsize) -> Self {
+        self.max_open_handles = Some(max_open_handles);
+        self
+    }
+
+    pub(super) fn get_max_open_handles(&self) -> Option<usize> {
+        self.max_open_handles.map(NonZeroUsize::get)
+    }
+
+    /// Set `upload_rate_limit` in bytes per second, which limits the
+    /// bytes sent to the server, including the headers of the requests.
+    ///
+    /// It applies to all requests sent by [`super::Sftp`] and can be
+    /// changed later via [`super::Sftp::set_upload_rate_limit`].
+    ///
+    /// At most one second worth of bytes is sent in a burst.
+    ///
+    /// It is unlimited by default.
+    #[must_use]
+    pub const fn upload_rate_limit(mut self, upload_rate_limit: NonZeroU64) -> Self {
+        self.upload_rate_limit = Some(upload_rate_limit);
+        self
+    }
+
+    pub(super) fn get_upload_rate_limit(&self) -> Option<NonZeroU64> {
+        self.upload_rate_limit
+    }
+
+    /// Set `download_rate_limit` in bytes per second, which limits the
+    /// bytes received from the server, including the headers of the responses.
+    ///
+    /// It applies to all responses received by [`super::Sftp`] and can be
+    /// changed later via [`super::Sftp::set_download_rate_limit`].
+    ///
+    /// At most one second worth of bytes is received in a burst.
+    ///
+    /// It is unlimited by default.
+    #[must_use]
+    pub const fn download_rate_limit(mut self, download_rate_limit: NonZeroU64) -> Self {
+        self.download_rate_limit = Some(download_rate_limit);
+        self
+    }
+
+    pub(super) fn get_download_rate_limit(&self) -> Option<NonZeroU64> {
+        self.download_rate_limit
+    }
 }
 
 #[cfg(feature = "__ci-tests")]
@@ -0,0 +1,195 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Minimum duration used to calculate [`TransferProgress::rate`].
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Snapshot of the progress of a transfer, passed to
+/// [`ProgressObserver::on_progress`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TransferProgress {
+    bytes: u64,
+    total: Option<u64>,
+    rate: f64,
+    elapsed: Duration,
+}
+
+impl TransferProgress {
+    /// Return number of bytes transferred so far.
+    ///
+    /// Written bytes are only counted once acknowledged by the server,
+    /// while read bytes are counted once received.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Return total number of bytes to transfer, if known.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Return the current rate in bytes per second, calculated over
+    /// roughly the last second.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Return time elapsed since the [`Progress`] is created.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// Observer notified whenever a transfer makes progress.
+///
+/// It is implemented for closures taking [`TransferProgress`].
+///
+/// It is called on the task doing the transfer, so it should not block.
+pub trait ProgressObserver: Send + Sync {
+    /// Called after more bytes are transferred.
+    fn on_progress(&self, progress: TransferProgress);
+}
+
+impl<F> ProgressObserver for F
+where
+    F: Fn(TransferProgress) + Send + Sync,
+{
+    fn on_progress(&self, progress: TransferProgress) {
+        self(progress)
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    bytes: u64,
+    total: Option<u64>,
+    start: Instant,
+    window_start: Instant,
+    window_bytes: u64,
+    rate: f64,
+}
+
+impl State {
+    fn snapshot(&self, now: Instant) -> TransferProgress {
+        TransferProgress {
+            bytes: self.bytes,
+            total: self.total,
+            rate: self.rate,
+            elapsed: now.duration_since(self.start),
+        }
+    }
+}
+
+struct Inner {
+    observer: Box<dyn ProgressObserver>,
+    state: Mutex<State>,
+}
+
+/// Progress of transfers reported to a [`ProgressObserver`].
+///
+/// It can be attached to a [`File`](crate::file::File) using
+/// [`File::set_progress`](crate::file::File::set_progress), which also
+/// applies to [`TokioCompatFile`](crate::file::TokioCompatFile), or to
+/// [`Uploader`](crate::fs::Uploader) and [`Downloader`](crate::fs::Downloader).
+///
+/// Cloning it returns a handle to the same progress, so it can be attached
+/// to multiple files to report their combined progress.
+#[derive(Clone)]
+pub struct Progress(Arc<Inner>);
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Progress").field(&self.0.state).finish()
+    }
+}
+
+impl Progress {
+    /// Create a new [`Progress`] reporting to `observer`.
+    pub fn new(observer: impl ProgressObserver + 'static) -> Self {
+        let now = Instant::now();
+
+        Self(Arc::new(Inner {
+            observer: Box::new(observer),
+            state: Mutex::new(State {
+                bytes: 0,
+                total: None,
+                start: now,
+                window_start: now,
+                window_bytes: 0,
+                rate: 0.0,
+            }),
+        }))
+    }
+
+    /// Set total number of bytes to transfer.
+    pub fn set_total(&self, total: Option<u64>) {
+        self.0.state.lock().unwrap().total = total;
+    }
+
+    /// Return the current progress.
+    pub fn snapshot(&self) -> TransferProgress {
+        self.0.state.lock().unwrap().snapshot(Instant::now())
+    }
+
+    /// Add `n` bytes to the progress and notify the observer.
+    pub(crate) fn add(&self, n: u64) {
+        if n == 0 {
+            return;
+        }
+
+        let progress = {
+            let mut state = self.0.state.lock().unwrap();
+            let now = Instant::now();
+
+            state.bytes += n;
+            state.window_bytes += n;
+
+            let window = now.duration_since(state.window_start);
+            if window >= RATE_WINDOW {
+                state.rate = state.window_bytes as f64 / window.as_secs_f64();
+                state.window_start = now;
+                state.window_bytes = 0;
+            } else if state.window_start == state.start && !window.is_zero() {
+                // The first window is not complete yet.
+                state.rate = state.window_bytes as f64 / window.as_secs_f64();
+            }
+
+            state.snapshot(now)
+        };
+
+        // Called without the lock so that the observer can use `self`.
+        self.0.observer.on_progress(progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+
+        let progress = {
+            let reports = Arc::clone(&reports);
+            Progress::new(move |progress: TransferProgress| {
+                reports.lock().unwrap().push(progress.bytes())
+            })
+        };
+        progress.set_total(Some(10));
+
+        progress.clone().add(3);
+        progress.add(0);
+        progress.add(7);
+
+        assert_eq!(*reports.lock().unwrap(), [3, 10]);
+
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.bytes(), 10);
+        assert_eq!(snapshot.total(), Some(10));
+        assert!(snapshot.rate() >= 0.0);
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    future::Future,
+    io,
+    num::NonZeroU64,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    io::{AsyncRead, ReadBuf},
+    time::{sleep, Sleep},
+};
+
+/// Maximum time to sleep before checking the bucket again, so that
+/// changes to the rate limit take effect quickly.
+const MAX_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct Bucket {
+    rate: Option<NonZeroU64>,
+    /// Number of bytes that can be transferred without waiting.
+    ///
+    /// It is negative if more bytes than allowed have been transferred,
+    /// which has to be paid back before transferring anything else.
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn new(now: Instant) -> Self {
+        Self {
+            rate: None,
+            tokens: 0.0,
+            last: now,
+        }
+    }
+
+    /// Refill the bucket, which holds at most one second worth of bytes.
+    fn refill(&mut self, now: Instant) {
+        if let Some(rate) = self.rate {
+            let rate = rate.get() as f64;
+            let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+
+            self.tokens = (self.tokens + elapsed * rate).min(rate);
+        }
+        self.last = now;
+    }
+
+    fn set_rate(&mut self, rate: Option<NonZeroU64>, now: Instant) {
+        self.refill(now);
+
+        self.tokens = match (self.rate, rate) {
+            (_, None) => 0.0,
+            // Start with a full bucket.
+            (None, Some(rate)) => rate.get() as f64,
+            // Keep the debt, which is now paid back at the new rate.
+            (Some(_), Some(rate)) => self.tokens.min(rate.get() as f64),
+        };
+        self.rate = rate;
+    }
+
+    fn consume(&mut self, n: usize, now: Instant) {
+        self.refill(now);
+
+        if self.rate.is_some() {
+            self.tokens -= n as f64;
+        }
+    }
+
+    /// Return time to wait until the debt is paid back.
+    fn delay(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+
+        let rate = self.rate?.get() as f64;
+        (self.tokens < 0.0).then(|| Duration::from_secs_f64(-self.tokens / rate))
+    }
+}
+
+/// Token bucket limiting the number of bytes transferred per second.
+#[derive(Debug)]
+pub(super) struct RateLimiter {
+    /// Same as `bucket.rate`, 0 if unlimited.
+    ///
+    /// Used to avoid locking the bucket if there is no limit.
+    rate: AtomicU64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub(super) fn new() -> Self {
+        Self {
+            rate: AtomicU64::new(0),
+            bucket: Mutex::new(Bucket::new(Instant::now())),
+        }
+    }
+
+    pub(super) fn rate(&self) -> Option<NonZeroU64> {
+        NonZeroU64::new(self.rate.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn set_rate(&self, rate: Option<NonZeroU64>) {
+        let mut bucket = self.bucket.lock().unwrap();
+
+        bucket.set_rate(rate, Instant::now());
+        self.rate
+            .store(rate.map_or(0, NonZeroU64::get), Ordering::Relaxed);
+    }
+
+    /// Take `n` bytes out of the bucket, without waiting.
+    pub(super) fn consume(&self, n: usize) {
+        if self.rate().is_some() && n != 0 {
+            self.bucket.lock().unwrap().consume(n, Instant::now());
+        }
+    }
+
+    /// Return time to wait before transferring more bytes, capped to
+    /// [`MAX_DELAY`].
+    pub(super) fn delay(&self) -> Option<Duration> {
+        self.rate()?;
+
+        self.bucket
+            .lock()
+            .unwrap()
+            .delay(Instant::now())
+            .map(|delay| delay.min(MAX_DELAY))
+    }
+
+    /// Wait until the debt is paid back, then take `n` bytes out of the bucket.
+    pub(super) async fn acquire(&self, n: usize) {
+        while let Some(delay) = self.delay() {
+            sleep(delay).await;
+        }
+        self.consume(n);
+    }
+}
+
+/// Reader that stops reading while `limiter` is in debt.
+#[derive(Debug)]
+pub(super) struct RateLimitedReader<'a, R> {
+    reader: R,
+    limiter: &'a RateLimiter,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<'a, R> RateLimitedReader<'a, R> {
+    pub(super) fn new(reader: R, limiter: &'a RateLimiter) -> Self {
+        Self {
+            reader,
+            limiter,
+            sleep: None,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for RateLimitedReader<'_, R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(sleep) = this.sleep.as_mut() {
+                ready!(sleep.as_mut().poll(cx));
+                this.sleep = None;
+            }
+
+            match this.limiter.delay() {
+                Some(delay) => this.sleep = Some(Box::pin(sleep(delay))),
+                None => break,
+            }
+        }
+
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.reader).poll_read(cx, buf))?;
+        this.limiter.consume(buf.filled().len() - filled);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(start);
+
+        // Unlimited
+        bucket.consume(1000, start);
+        assert_eq!(bucket.delay(start), None);
+
+        let rate = NonZeroU64::new(100);
+        bucket.set_rate(rate, start);
+
+        // One second worth of bytes can be transferred without waiting.
+        bucket.consume(100, start);
+        assert_eq!(bucket.delay(start), None);
+
+        bucket.consume(50, start);
+        assert_eq!(bucket.delay(start), Some(Duration::from_millis(500)));
+
+        let now = start + Duration::from_millis(250);
+        assert_eq!(bucket.delay(now), Some(Duration::from_millis(250)));
+
+        // The debt is paid back at the new rate.
+        bucket.set_rate(NonZeroU64::new(25), now);
+        assert_eq!(bucket.delay(now), Some(Duration::from_secs(1)));
+
+        // The bucket never holds more than one second worth of bytes.
+        let now = now + Duration::from_secs(10);
+        bucket.consume(50, now);
+        assert_eq!(bucket.delay(now), Some(Duration::from_secs(1)));
+
+        // Removing the limit clears the debt.
+        bucket.set_rate(None, now);
+        assert_eq!(bucket.delay(now), None);
+    }
+}
@@ -1,25 +1,26 @@
 use crate::{
     auxiliary,
     file::{File, OpenOptions},
-    fs::Fs,
+    fs::{checksum_algorithms, Fs, HOME_DIRECTORY, USERS_GROUPS_BY_ID},
     lowlevel, tasks,
     utils::{ErrorExt, ResultExt},
     Error, MpscQueue, SftpOptions, SharedData, WriteEnd, WriteEndWithCachedId,
 };
 
 use auxiliary::Auxiliary;
-use lowlevel::{connect, Extensions};
+use lowlevel::{connect_with_version, Extensions};
 use tasks::{create_flush_task, create_read_task};
 
 use std::{
-    any::Any, convert::TryInto, fmt, future::Future, ops::Deref, path::Path, pin::Pin, sync::Arc,
+    any::Any, convert::TryInto, fmt, future::Future, num::NonZeroU64, ops::Deref, path::Path,
+    pin::Pin, sync::Arc,
 };
 
 use derive_destructure2::destructure;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     runtime::Handle,
-    sync::oneshot::Receiver,
+    sync::{oneshot::Receiver, Semaphore},
     task::JoinHandle,
 };
 use tokio_io_utility::assert_send;
@@ -147,8 +148,18 @@ impl Sftp {
                 options.get_max_pending_requests(),
                 auxiliary,
                 options.get_tokio_compat_file_write_limit(),
+                options.get_cache_names(),
+                options.get_max_sftp_version(),
             ))?;
 
+            let auxiliary = write_end.get_auxiliary();
+            auxiliary
+                .upload_limiter()
+                .set_rate(options.get_upload_rate_limit());
+            auxiliary
+                .download_limiter()
+                .set_rate(options.get_download_rate_limit());
+
             let flush_task = create_flush_task(
                 stdin,
                 SharedData::clone(&write_end),
@@ -172,15 +183,19 @@ impl Sftp {
         max_pending_requests: u16,
         auxiliary: SftpAuxiliaryData,
         tokio_compat_file_write_limit: usize,
+        cache_names: bool,
+        max_sftp_version: u32,
     ) -> Result<WriteEnd, Error> {
-        connect(
+        connect_with_version(
             MpscQueue::with_capacity(write_end_buffer_size),
             Auxiliary::new(
                 max_pending_requests,
                 auxiliary,
                 tokio_compat_file_write_limit,
+                cache_names,
                 Handle::current(),
             ),
+            max_sftp_version,
         )
     }
 
@@ -237,34 +252,44 @@ impl Sftp {
         // is at least 9 bytes long.
         let default_max_packet_len = u32::MAX - 9;
 
-        let (read_len, write_len, packet_len) = if extensions.contains(Extensions::LIMITS) {
-            let mut limits = write_end
-                .send_request(|write_end, id| Ok(write_end.send_limits_request(id)?.wait()))
-                .await?;
-
-            if limits.read_len == 0 {
-                limits.read_len = default_download_buflen;
-            }
-
-            if limits.write_len == 0 {
-                limits.write_len = default_upload_buflen;
-            }
-
-            (
-                limits.read_len,
-                limits.write_len,
-                limits
-                    .packet_len
-                    .try_into()
-                    .unwrap_or(default_max_packet_len),
-            )
-        } else {
-            (
-                default_download_buflen,
-                default_upload_buflen,
-                default_max_packet_len,
-            )
-        };
+        let (read_len, write_len, packet_len, open_handles) =
+            if extensions.contains(Extensions::LIMITS) {
+                let mut limits = write_end
+                    .send_request(|write_end, id| Ok(write_end.send_limits_request(id)?.wait()))
+                    .await?;
+
+                if limits.read_len == 0 {
+                    limits.read_len = default_download_buflen;
+                }
+
+                if limits.write_len == 0 {
+                    limits.write_len = default_upload_buflen;
+                }
+
+                (
+                    limits.read_len,
+                    limits.write_len,
+                    limits
+                        .packet_len
+                        .try_into()
+                        .unwrap_or(default_max_packet_len),
+                    // 0 means unlimited.
+                    Some(limits.open_handles)
+                        .filter(|open_handles| *open_handles != 0)
+                        .map(|open_handles| {
+                            open_handles
+                                .try_into()
+                                .unwrap_or(auxiliary::UNLIMITED_OPEN_HANDLES)
+                        }),
+                )
+            } else {
+                (
+                    default_download_buflen,
+                    default_upload_buflen,
+                    default_max_packet_len,
+                    None,
+                )
+            };
 
         // Each read/write request also has a header and contains a handle,
         // which is 4-byte long for openssh but can be at most 256 bytes long
@@ -282,15 +307,29 @@ impl Sftp {
             .map(|v| v.min(write_len))
             .unwrap_or(write_len);
 
+        let open_handles = options
+            .get_max_open_handles()
+            .or(open_handles)
+            .map(|open_handles| open_handles.min(auxiliary::UNLIMITED_OPEN_HANDLES));
+
         let limits = auxiliary::Limits {
             read_len,
             write_len,
+            open_handles,
         };
 
+        let handle_semaphore = Arc::new(Semaphore::new(
+            open_handles.unwrap_or(auxiliary::UNLIMITED_OPEN_HANDLES),
+        ));
+
         write_end
             .get_auxiliary()
             .conn_info
-            .set(auxiliary::ConnInfo { limits, extensions })
+            .set(auxiliary::ConnInfo {
+                limits,
+                extensions,
+                handle_semaphore,
+            })
             .expect("auxiliary.conn_info shall be uninitialized");
 
         Ok(())
@@ -392,6 +431,59 @@ impl Sftp {
         Fs::new(self.handle.clone().write_end(), "".into())
     }
 
+    /// Return the negotiated sftp protocol version.
+    ///
+    /// It is the lower one of [`SftpOptions::max_sftp_version`] and
+    /// the version the server supports.
+    pub fn sftp_version(&self) -> u32 {
+        self.handle.sftp_version()
+    }
+
+    /// Return the sftp protocol version sent by the server, which might be
+    /// newer than [`Sftp::sftp_version`].
+    pub fn server_version(&self) -> u32 {
+        self.handle
+            .server_version()
+            .expect("server hello shall be received by sftp::Sftp::new")
+    }
+
+    /// Return (name, data) of all extensions advertised by the server,
+    /// including the ones not supported by this crate.
+    ///
+    /// The data is usually the version of the extension.
+    pub fn server_extensions(&self) -> impl Iterator<Item = (&str, &[u8])> + '_ {
+        self.handle.server_extensions()
+    }
+
+    /// Return data of extension `name` advertised by the server, or `None`
+    /// if the server does not advertise it.
+    pub fn server_extension_data(&self, name: &str) -> Option<&[u8]> {
+        self.handle.server_extension_data(name)
+    }
+
+    /// Return the maximum number of files and directories that can be
+    /// open at the same time, or `None` if it is unlimited.
+    ///
+    /// See [`SftpOptions::max_open_handles`].
+    pub fn max_open_handles(&self) -> Option<usize> {
+        self.handle.get_auxiliary().limits().open_handles
+    }
+
+    /// Return number of files and directories currently open.
+    ///
+    /// A handle is counted until its close request is answered by the server.
+    pub fn open_handles(&self) -> usize {
+        self.handle.get_auxiliary().open_handles()
+    }
+
+    /// Return hash algorithms supported by [`Fs::checksum`] and
+    /// [`File::checksum`], as advertised by the server.
+    ///
+    /// `md5` is included if the server supports the md5-hash extension.
+    pub fn checksum_algorithms(&self) -> Vec<&str> {
+        checksum_algorithms(&self.handle)
+    }
+
     /// Check if the remote server supports the expand path extension.
     ///
     /// If it returns true, then [`Fs::canonicalize`] with expand path is supported.
@@ -441,6 +533,83 @@ impl Sftp {
             .extensions()
             .contains(Extensions::COPY_DATA)
     }
+
+    /// Check if the remote server supports the statvfs extension.
+    ///
+    /// If it returns true, then [`Fs::statvfs`] is supported.
+    pub fn support_statvfs(&self) -> bool {
+        self.handle
+            .get_auxiliary()
+            .extensions()
+            .contains(Extensions::STATVFS)
+    }
+
+    /// Check if the remote server supports the fstatvfs extension.
+    ///
+    /// If it returns true, then [`File::statvfs`] is supported.
+    pub fn support_fstatvfs(&self) -> bool {
+        self.handle
+            .get_auxiliary()
+            .extensions()
+            .contains(Extensions::FSTATVFS)
+    }
+
+    /// Check if the remote server supports the lsetstat extension.
+    ///
+    /// If it returns true, then [`Fs::set_symlink_metadata`] is supported.
+    pub fn support_lsetstat(&self) -> bool {
+        self.handle
+            .get_auxiliary()
+            .extensions()
+            .contains(Extensions::LSETSTAT)
+    }
+
+    /// Check if the remote server supports the users-groups-by-id extension.
+    ///
+    /// If it returns true, then [`Fs::lookup_names`] is supported.
+    pub fn support_users_groups_by_id(&self) -> bool {
+        self.handle
+            .server_extension_data(USERS_GROUPS_BY_ID)
+            .is_some()
+    }
+
+    /// Check if the remote server supports the home-directory extension.
+    ///
+    /// If it returns true, then [`Fs::home_dir`] uses it.
+    pub fn support_home_directory(&self) -> bool {
+        self.handle.server_extension_data(HOME_DIRECTORY).is_some()
+    }
+
+    /// Return the limit of bytes sent per second, `None` if unlimited.
+    pub fn upload_rate_limit(&self) -> Option<NonZeroU64> {
+        self.handle.get_auxiliary().upload_limiter().rate()
+    }
+
+    /// Change the limit of bytes sent per second, `None` to remove the limit.
+    ///
+    /// It takes effect immediately, including for requests already queued.
+    ///
+    /// See [`SftpOptions::upload_rate_limit`] for more information.
+    pub fn set_upload_rate_limit(&self, limit: Option<NonZeroU64>) {
+        self.handle.get_auxiliary().upload_limiter().set_rate(limit)
+    }
+
+    /// Return the limit of bytes received per second, `None` if unlimited.
+    pub fn download_rate_limit(&self) -> Option<NonZeroU64> {
+        self.handle.get_auxiliary().download_limiter().rate()
+    }
+
+    /// Change the limit of bytes received per second, `None` to remove the limit.
+    ///
+    /// It takes effect immediately, including for responses not yet received.
+    ///
+    /// See [`SftpOptions::download_rate_limit`] for more information.
+    pub fn set_download_rate_limit(&self, limit: Option<NonZeroU64>) {
+        self.handle
+            .get_auxiliary()
+            .download_limiter()
+            .set_rate(limit)
+    }
 }
 
 #[cfg(feature = "__ci-tests")]
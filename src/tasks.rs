@@ -1,4 +1,4 @@
-use super::{lowlevel::Extensions, Error, ReadEnd, SharedData};
+use super::{lowlevel::Extensions, rate_limit::RateLimitedReader, Error, ReadEnd, SharedData};
 
 use std::{
     num::NonZeroUsize,
@@ -26,6 +26,15 @@ async fn flush(
 ) -> Result<(), Error> {
     shared_data.queue().swap(buffer);
 
+    if !buffer.is_empty() {
+        let len = buffer.iter().map(Bytes::len).sum();
+        shared_data
+            .get_auxiliary()
+            .upload_limiter()
+            .acquire(len)
+            .await;
+    }
+
     #[cfg(feature = "tracing")]
     tracing::debug!(
         "Flushing out {} bytes, shared_data = {shared_data:p}",
@@ -170,9 +179,11 @@ pub(super) fn create_read_task<R: AsyncRead + Send + 'static>(
         shared_data: SharedData,
         tx: oneshot::Sender<Extensions>,
     ) -> Result<(), Error> {
+        let auxiliary = shared_data.get_auxiliary();
+
+        let stdout = RateLimitedReader::new(stdout, auxiliary.download_limiter());
         let read_end = ReadEnd::new(stdout, read_end_buffer_size, shared_data.clone());
 
-        let auxiliary = shared_data.get_auxiliary();
         let read_end_notify = &auxiliary.read_end_notify;
         let requests_to_read = &auxiliary.requests_to_read;
         let shutdown_stage = &auxiliary.shutdown_stage;
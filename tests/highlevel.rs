@@ -3,16 +3,17 @@ use std::{
     cmp::{max, min},
     convert::{identity, TryInto},
     env, fs,
-    future::ready,
+    future::{poll_fn, ready},
     io::IoSlice,
     num::{NonZeroU32, NonZeroU64, NonZeroUsize},
     path::Path,
     path::PathBuf,
     stringify,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
+use futures_sink::Sink;
 use futures_util::StreamExt;
 use openssh::{KnownHosts, Session, SessionBuilder};
 use openssh_sftp_client::*;
@@ -20,7 +21,7 @@ use pretty_assertions::assert_eq;
 use sftp_test_common::*;
 use tokio::{
     io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
-    time::sleep,
+    time::{sleep, timeout},
 };
 use tokio_io_utility::write_vectored_all;
 
@@ -540,6 +541,1385 @@ async fn sftp_fs_metadata() {
     assert!(child.wait().await.unwrap().success());
 }
 
+#[tokio::test]
+/// Test Fs::set_symlink_metadata.
+async fn sftp_fs_set_symlink_metadata() {
+    let filename = gen_path("sftp_fs_set_symlink_metadata_file");
+    let symlink = gen_path("sftp_fs_set_symlink_metadata_symlink");
+
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    assert!(sftp.support_lsetstat());
+
+    {
+        let mut fs = sftp.fs();
+
+        fs.write(&filename, b"hello, world!\n").await.unwrap();
+        fs.symlink(&filename, &symlink).await.unwrap();
+
+        let file_modified = fs.metadata(&filename).await.unwrap().modified().unwrap();
+
+        let timestamp = UnixTimeStamp::from_raw(1000).unwrap();
+        fs.set_symlink_metadata(
+            &symlink,
+            metadata::MetaDataBuilder::new()
+                .time(timestamp, timestamp)
+                .create(),
+        )
+        .await
+        .unwrap();
+
+        let metadata = fs.symlink_metadata(&symlink).await.unwrap();
+        assert!(metadata.file_type().unwrap().is_symlink());
+        assert_eq!(metadata.modified().unwrap(), timestamp);
+
+        // The target of the symlink is left untouched
+        assert_eq!(
+            fs.metadata(&filename).await.unwrap().modified().unwrap(),
+            file_modified
+        );
+
+        fs.remove_file(&symlink).await.unwrap();
+        fs.remove_file(&filename).await.unwrap();
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::statvfs and File::statvfs.
+async fn sftp_fs_statvfs() {
+    let path = gen_path("sftp_fs_statvfs");
+
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    assert!(sftp.support_statvfs());
+    assert!(sftp.support_fstatvfs());
+
+    {
+        let mut fs = sftp.fs();
+
+        let mut file = sftp.create(&path).await.unwrap();
+
+        let stats = fs.statvfs(&path).await.unwrap();
+        let fstats = file.statvfs().await.unwrap();
+
+        assert_eq!(stats.fsid(), fstats.fsid());
+        assert_eq!(stats.block_size(), fstats.block_size());
+        assert!(stats.blocks() >= stats.blocks_free());
+        assert!(stats.blocks_free() >= stats.blocks_available());
+        assert!(stats.total_space() >= stats.free_space());
+
+        fs.remove_file(&path).await.unwrap();
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::lookup_names with names cache enabled.
+async fn sftp_fs_lookup_names() {
+    let path = gen_path("sftp_fs_lookup_names");
+
+    let (mut child, sftp) = connect(SftpOptions::new().cache_names(true)).await;
+
+    assert!(sftp.support_users_groups_by_id());
+
+    {
+        let mut fs = sftp.fs();
+
+        fs.write(&path, "").await.unwrap();
+
+        let metadata = fs.metadata(&path).await.unwrap();
+        let uid = metadata.uid().unwrap();
+        let gid = metadata.gid().unwrap();
+
+        let (users, groups) = fs.lookup_names(&[uid, uid], &[gid]).await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(groups.len(), 1);
+        assert!(!users[&uid].is_empty());
+        assert!(!groups[&gid].is_empty());
+
+        // Second lookup is served from the cache.
+        let (cached_users, cached_groups) = fs.lookup_names(&[uid], &[gid]).await.unwrap();
+        assert_eq!(cached_users, users);
+        assert_eq!(cached_groups, groups);
+
+        fs.remove_file(&path).await.unwrap();
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::home_dir
+async fn sftp_fs_home_dir() {
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    assert!(sftp.support_home_directory());
+
+    {
+        let mut fs = sftp.fs();
+
+        let (home, method) = fs.home_dir(None).await.unwrap();
+        assert_eq!(
+            method,
+            openssh_sftp_client::fs::HomeDirMethod::HomeDirectory
+        );
+        assert!(home.is_absolute());
+
+        assert_eq!(fs.canonicalize("~").await.unwrap(), home);
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test SftpOptions::max_open_handles
+async fn sftp_max_open_handles() {
+    let path = gen_path("sftp_max_open_handles");
+
+    let options = SftpOptions::new().max_open_handles(NonZeroUsize::new(1).unwrap());
+    let (mut child, sftp) = connect(options).await;
+
+    assert_eq!(sftp.max_open_handles(), Some(1));
+    assert_eq!(sftp.open_handles(), 0);
+
+    sftp.fs().create_dir(&path).await.unwrap();
+
+    let file = sftp.create(path.join("file1")).await.unwrap();
+    assert_eq!(sftp.open_handles(), 1);
+
+    {
+        // No free slot until file1 is closed.
+        let future = sftp.create(path.join("file2"));
+        tokio::pin!(future);
+
+        assert!(timeout(Duration::from_millis(100), &mut future)
+            .await
+            .is_err());
+
+        file.close().await.unwrap();
+
+        let file = future.await.unwrap();
+        assert_eq!(sftp.open_handles(), 1);
+
+        file.close().await.unwrap();
+        assert_eq!(sftp.open_handles(), 0);
+    }
+
+    // Dropping a file releases its slot once the close request is answered.
+    let mut fs = sftp.fs();
+    drop(fs.open_dir(&path).await.unwrap());
+
+    timeout(Duration::from_secs(10), fs.open_dir(&path))
+        .await
+        .unwrap()
+        .unwrap();
+
+    drop(fs);
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test SftpOptions::max_sftp_version
+async fn sftp_max_sftp_version() {
+    let path = gen_path("sftp_max_sftp_version");
+    let content = b"hello, world!\n";
+
+    let (mut child, sftp) = connect(SftpOptions::new().max_sftp_version(6)).await;
+
+    // openssh only speaks sftp v3
+    assert_eq!(sftp.sftp_version(), 3);
+
+    {
+        let mut fs = sftp.fs();
+
+        fs.write(&path, content).await.unwrap();
+
+        let metadata = fs.metadata(&path).await.unwrap();
+        assert_eq!(metadata.len().unwrap(), content.len() as u64);
+        assert!(metadata.owner().is_none());
+        assert!(metadata.created().is_none());
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+
+    let (child, stdin, stdout) = launch_sftp().await;
+    assert!(matches!(
+        Sftp::new(stdin, stdout, SftpOptions::new().max_sftp_version(7)).await,
+        Err(Error::UnsupportedSftpProtocol { version: 7 })
+    ));
+    drop(child);
+}
+
+#[tokio::test]
+/// Test Sftp::server_version and Sftp::server_extensions
+async fn sftp_server_extensions() {
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    assert_eq!(sftp.server_version(), 3);
+
+    let extensions: Vec<_> = sftp.server_extensions().collect();
+    eprintln!("{:#?}", extensions);
+
+    assert!(extensions.contains(&("posix-rename@openssh.com", &b"1"[..])));
+    assert_eq!(
+        sftp.server_extension_data("limits@openssh.com"),
+        Some(&b"1"[..])
+    );
+    assert_eq!(sftp.server_extension_data("no-such-extension"), None);
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::checksum and File::checksum
+async fn sftp_checksum() {
+    let path = gen_path("sftp_checksum");
+    let content = b"hello, world!\n";
+
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    // openssh supports neither check-file nor md5-hash
+    assert!(sftp.checksum_algorithms().is_empty());
+
+    {
+        let mut fs = sftp.fs();
+
+        fs.write(&path, content).await.unwrap();
+
+        let err = fs.checksum(&path, "md5", .., 0).await.unwrap_err();
+        assert!(
+            matches!(err, Error::UnsupportedExtension(name) if *name == "check-file"),
+            "{:#?}",
+            err
+        );
+
+        let mut file = sftp.open(&path).await.unwrap();
+
+        let err = file.checksum("sha256,md5", 0..4, 0).await.unwrap_err();
+        assert!(
+            matches!(err, Error::UnsupportedExtension(name) if *name == "check-file"),
+            "{:#?}",
+            err
+        );
+
+        assert!(matches!(
+            file.checksum("md5", 4..4, 0).await.unwrap_err(),
+            Error::IOError(_)
+        ));
+
+        file.close().await.unwrap();
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::copy and Fs::copy_with_permissions
+async fn sftp_fs_copy() {
+    let path = gen_path("sftp_fs_copy");
+    let src = path.join("src");
+    let dst = path.join("dst");
+    let content: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+
+    let expected_method = if sftp.server_extension_data("copy-file").is_some() {
+        openssh_sftp_client::fs::CopyMethod::CopyFile
+    } else if sftp.support_copy() {
+        openssh_sftp_client::fs::CopyMethod::CopyData
+    } else {
+        openssh_sftp_client::fs::CopyMethod::ReadWrite
+    };
+
+    {
+        let mut fs = sftp.fs();
+
+        fs.create_dir(&path).await.unwrap();
+        fs.write(&src, &content).await.unwrap();
+        fs.set_permissions(&src, metadata::Permissions::from(0o640))
+            .await
+            .unwrap();
+
+        let method = fs.copy(&src, &dst, false).await.unwrap();
+        assert_eq!(method, expected_method);
+        assert_eq!(&*fs.read(&dst).await.unwrap(), &*content);
+
+        // dst already exists
+        fs.copy(&src, &dst, false).await.unwrap_err();
+
+        fs.write(&dst, b"hello").await.unwrap();
+        fs.copy_with_permissions(&src, &dst, true).await.unwrap();
+        assert_eq!(&*fs.read(&dst).await.unwrap(), &*content);
+        assert_eq!(
+            fs.metadata(&dst).await.unwrap().permissions(),
+            fs.metadata(&src).await.unwrap().permissions()
+        );
+
+        // dst is longer than src
+        fs.write(&dst, [content.as_slice(), b"hello"].concat())
+            .await
+            .unwrap();
+        fs.copy(&src, &dst, true).await.unwrap();
+        assert_eq!(&*fs.read(&dst).await.unwrap(), &*content);
+
+        // src and dst are the same file, which the server might refuse,
+        // but it must not lose any data.
+        let _ = fs.copy(&src, &src, true).await;
+        assert_eq!(&*fs.read(&src).await.unwrap(), &*content);
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::walk_dir
+async fn sftp_fs_walk_dir() {
+    let path = gen_path("sftp_fs_walk_dir");
+
+    fs::create_dir_all(path.join("a/b")).unwrap();
+    fs::create_dir_all(path.join("c")).unwrap();
+    fs::write(path.join("a/file"), b"hello").unwrap();
+    fs::write(path.join("a/b/file"), b"hello").unwrap();
+    std::os::unix::fs::symlink(&path, path.join("c/loop")).unwrap();
+
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    let root = &path;
+    let walk = |walk_dir: openssh_sftp_client::fs::WalkDir| async move {
+        walk_dir
+            .into_stream()
+            .map(|res| {
+                let entry = res?;
+                let relative = entry.path().strip_prefix(root).unwrap().to_owned();
+                Ok((relative, entry.depth()))
+            })
+            .collect::<Vec<Result<(PathBuf, usize), Error>>>()
+            .await
+    };
+    let position = |entries: &[(PathBuf, usize)], p: &str| {
+        entries
+            .iter()
+            .position(|(relative, _)| relative == Path::new(p))
+            .unwrap()
+    };
+
+    {
+        let fs = sftp.fs();
+
+        // Pre-order
+        let entries: Vec<_> = walk(
+            fs.walk_dir(&path)
+                .max_concurrent_dirs(NonZeroUsize::new(1).unwrap()),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+        assert_eq!(entries.len(), 7);
+        assert_eq!(entries[0], (PathBuf::new(), 0));
+        assert!(entries.contains(&(PathBuf::from("a/b/file"), 3)));
+        assert!(position(&entries, "a") < position(&entries, "a/b"));
+        assert!(position(&entries, "a/b") < position(&entries, "a/b/file"));
+
+        // Post-order
+        let entries: Vec<_> = walk(fs.walk_dir(&path).contents_first(true))
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 7);
+        assert_eq!(entries[6], (PathBuf::new(), 0));
+        assert!(position(&entries, "a/b/file") < position(&entries, "a/b"));
+        assert!(position(&entries, "a/b") < position(&entries, "a"));
+
+        // Depth limit
+        let entries = walk(fs.walk_dir(&path).max_depth(1)).await;
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|res| res.as_ref().unwrap().1 <= 1));
+
+        // Filter prunes the subtree
+        let entries: Vec<_> = walk(
+            fs.walk_dir(&path)
+                .filter_entry(|entry| entry.file_name() != "a"),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries
+            .iter()
+            .all(|(relative, _)| !relative.starts_with("a")));
+
+        // Follow symlinks with loop detection
+        let entries = walk(fs.walk_dir(&path).follow_links(true)).await;
+        assert_eq!(entries.len(), 7);
+        assert_eq!(entries.iter().filter(|res| res.is_err()).count(), 1);
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::remove_dir_all
+async fn sftp_fs_remove_dir_all() {
+    let path = gen_path("sftp_fs_remove_dir_all");
+    let target = gen_path("sftp_fs_remove_dir_all_target");
+
+    fs::create_dir_all(path.join("a/b")).unwrap();
+    fs::create_dir_all(&target).unwrap();
+    for i in 0..100 {
+        fs::write(path.join(format!("a/b/file{i}")), b"hello").unwrap();
+    }
+    fs::write(target.join("file"), b"hello").unwrap();
+    std::os::unix::fs::symlink(&target, path.join("a/link")).unwrap();
+
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    {
+        let mut fs = sftp.fs();
+
+        fs.remove_dir_all(&path).await.unwrap();
+        fs.symlink_metadata(&path).await.unwrap_err();
+
+        // Symlinks are not followed
+        assert!(target.join("file").exists());
+
+        // Non-existent path
+        assert!(matches!(
+            fs.remove_dir_all(&path).await.unwrap_err(),
+            Error::SftpError(error::SftpErrorKind::NoSuchFile, _)
+        ));
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::create_dir_all and DirBuilder::recursive
+async fn sftp_fs_create_dir_all() {
+    let path = gen_path("sftp_fs_create_dir_all");
+    let dir = path.join("a/b/c");
+
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    {
+        let mut fs = sftp.fs();
+
+        fs.create_dir(&dir).await.unwrap_err();
+
+        fs.create_dir_all(&dir).await.unwrap();
+        assert!(fs
+            .metadata(&dir)
+            .await
+            .unwrap()
+            .file_type()
+            .unwrap()
+            .is_dir());
+
+        // Existing directory
+        fs.create_dir_all(&dir).await.unwrap();
+        fs.create_dir_all(&path).await.unwrap();
+
+        // Existing file
+        fs.write(path.join("file"), b"hello").await.unwrap();
+        fs.create_dir_all(path.join("file")).await.unwrap_err();
+        fs.create_dir_all(path.join("file/d")).await.unwrap_err();
+
+        // Permissions are applied to every directory created
+        let perm = metadata::Permissions::from(0o750);
+        fs.dir_builder()
+            .recursive(true)
+            .permissions(perm)
+            .create(path.join("d/e"))
+            .await
+            .unwrap();
+        assert_eq!(
+            fs.metadata(path.join("d")).await.unwrap().permissions(),
+            Some(perm)
+        );
+        assert_eq!(
+            fs.metadata(path.join("d/e")).await.unwrap().permissions(),
+            Some(perm)
+        );
+
+        // Concurrent creation by multiple clients
+        let mut fs2 = sftp.fs();
+        let dir = path.join("f/g/h");
+        let (res1, res2) = tokio::join!(fs.create_dir_all(&dir), fs2.create_dir_all(&dir));
+        res1.unwrap();
+        res2.unwrap();
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::uploader
+async fn sftp_fs_upload() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let local = gen_path("sftp_fs_upload_local");
+    let remote = gen_path("sftp_fs_upload_remote");
+    let content: Vec<u8> = (0..20000).map(|i| i as u8).collect();
+
+    fs::create_dir_all(local.join("a/b")).unwrap();
+    for i in 0..20 {
+        fs::write(local.join(format!("a/b/file{i}")), &content).unwrap();
+    }
+    fs::write(local.join("file"), b"hello").unwrap();
+    fs::set_permissions(local.join("file"), fs::Permissions::from_mode(0o640)).unwrap();
+    std::os::unix::fs::symlink("file", local.join("link")).unwrap();
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+
+    {
+        let mut fs = sftp.fs();
+
+        let summary = fs
+            .uploader()
+            .max_concurrent_files(NonZeroUsize::new(4).unwrap())
+            .preserve(true)
+            .upload(&local, &remote)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.files(), 21);
+        assert_eq!(summary.dirs(), 3);
+        assert_eq!(summary.symlinks(), 1);
+        assert_eq!(summary.bytes(), 20 * 20000 + 5);
+
+        for i in 0..20 {
+            assert_eq!(
+                fs::read(remote.join(format!("a/b/file{i}"))).unwrap(),
+                content
+            );
+        }
+        assert_eq!(
+            fs::read_link(remote.join("link")).unwrap(),
+            Path::new("file")
+        );
+
+        let metadata = fs::metadata(remote.join("file")).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+        let secs = |time: std::time::SystemTime| {
+            time.duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        let local_modified = fs::metadata(local.join("file"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(secs(metadata.modified().unwrap()), secs(local_modified));
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::downloader
+async fn sftp_fs_download() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let remote = gen_path("sftp_fs_download_remote");
+    let local = gen_path("sftp_fs_download_local");
+    let content: Vec<u8> = (0..20000).map(|i| i as u8).collect();
+
+    fs::create_dir_all(remote.join("a/b")).unwrap();
+    for i in 0..20 {
+        fs::write(remote.join(format!("a/b/file{i}")), &content).unwrap();
+    }
+    fs::write(remote.join("file"), b"hello").unwrap();
+    fs::set_permissions(remote.join("file"), fs::Permissions::from_mode(0o640)).unwrap();
+    std::os::unix::fs::symlink("file", remote.join("link")).unwrap();
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+
+    {
+        let mut fs = sftp.fs();
+
+        let summary = fs
+            .downloader()
+            .max_concurrent_files(NonZeroUsize::new(4).unwrap())
+            .preserve(true)
+            .download(&remote, &local)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.files(), 21);
+        assert_eq!(summary.dirs(), 3);
+        assert_eq!(summary.symlinks(), 1);
+        assert_eq!(summary.bytes(), 20 * 20000 + 5);
+
+        for i in 0..20 {
+            assert_eq!(
+                fs::read(local.join(format!("a/b/file{i}"))).unwrap(),
+                content
+            );
+        }
+        assert_eq!(
+            fs::read_link(local.join("link")).unwrap(),
+            Path::new("file")
+        );
+
+        let metadata = fs::metadata(local.join("file")).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+
+        let secs = |time: std::time::SystemTime| {
+            time.duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        let remote_modified = fs::metadata(remote.join("file"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(secs(metadata.modified().unwrap()), secs(remote_modified));
+
+        // Follow symlinks
+        let summary = fs
+            .downloader()
+            .follow_links(true)
+            .download(&remote, local.join("followed"))
+            .await
+            .unwrap();
+        assert_eq!(summary.files(), 22);
+        assert_eq!(summary.symlinks(), 0);
+        assert_eq!(fs::read(local.join("followed/link")).unwrap(), b"hello");
+    }
+
+    // close sftp and child
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::glob
+async fn sftp_fs_glob() {
+    let path = gen_path("sftp_fs_glob");
+
+    fs::create_dir_all(path.join("logs/2023")).unwrap();
+    fs::create_dir_all(path.join("logs/.cache")).unwrap();
+    fs::write(path.join("logs/app-1.log"), b"1").unwrap();
+    fs::write(path.join("logs/app-2.log"), b"2").unwrap();
+    fs::write(path.join("logs/app-2.log.gz"), b"2").unwrap();
+    fs::write(path.join("logs/2023/app-3.log"), b"3").unwrap();
+    fs::write(path.join("logs/.cache/app-4.log"), b"4").unwrap();
+    fs::write(path.join("logs/*"), b"*").unwrap();
+
+    let (mut child, sftp) = connect(Default::default()).await;
+
+    let root = &path;
+    let glob = |pattern: &str| {
+        let stream = sftp.fs().glob(root.join(pattern));
+        async move {
+            let mut paths = stream
+                .map(|res| {
+                    let (path, metadata) = res.unwrap();
+                    assert!(metadata.len().is_some());
+                    path.strip_prefix(root).unwrap().to_owned()
+                })
+                .collect::<Vec<PathBuf>>()
+                .await;
+            paths.sort();
+            paths
+        }
+    };
+
+    assert_eq!(
+        glob("logs/app-*.log").await,
+        [
+            PathBuf::from("logs/app-1.log"),
+            PathBuf::from("logs/app-2.log")
+        ]
+    );
+    assert_eq!(
+        glob("logs/app-?.log*").await,
+        [
+            PathBuf::from("logs/app-1.log"),
+            PathBuf::from("logs/app-2.log"),
+            PathBuf::from("logs/app-2.log.gz")
+        ]
+    );
+    assert_eq!(
+        glob("logs/app-[!2].log").await,
+        [PathBuf::from("logs/app-1.log")]
+    );
+    assert_eq!(
+        glob("lo[a-z]s/*/app-*.log").await,
+        [PathBuf::from("logs/2023/app-3.log")]
+    );
+    assert_eq!(
+        glob("**/app-*.log").await,
+        [
+            PathBuf::from("logs/2023/app-3.log"),
+            PathBuf::from("logs/app-1.log"),
+            PathBuf::from("logs/app-2.log")
+        ]
+    );
+    assert_eq!(
+        glob("logs/.*/*.log").await,
+        [PathBuf::from("logs/.cache/app-4.log")]
+    );
+    assert_eq!(glob("logs/\\*").await, [PathBuf::from("logs/*")]);
+    assert_eq!(
+        glob("logs/app-1.log").await,
+        [PathBuf::from("logs/app-1.log")]
+    );
+    assert!(glob("logs/missing").await.is_empty());
+    assert!(glob("missing/*").await.is_empty());
+
+    // Relative to cwd
+    {
+        let mut fs = sftp.fs();
+        fs.set_cwd(path.join("logs"));
+
+        let mut paths = fs
+            .glob("app-*.log")
+            .map(|res| res.unwrap().0)
+            .collect::<Vec<PathBuf>>()
+            .await;
+        paths.sort();
+        assert_eq!(
+            paths,
+            [path.join("logs/app-1.log"), path.join("logs/app-2.log")]
+        );
+    }
+
+    // Tilde expansion
+    if sftp.support_expand_path() {
+        let home = sftp.fs().canonicalize("~").await.unwrap();
+        let paths = sftp
+            .fs()
+            .glob("~")
+            .map(|res| res.unwrap().0)
+            .collect::<Vec<PathBuf>>()
+            .await;
+        assert_eq!(paths, [home]);
+    }
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::reput and Fs::reget
+async fn sftp_fs_reput_reget() {
+    use openssh_sftp_client::fs::ResumeCheck;
+
+    let path = gen_path("sftp_fs_reput_reget");
+    fs::create_dir_all(&path).unwrap();
+
+    let content: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+    let src = path.join("src");
+    let dst = path.join("dst");
+
+    let mtime = |path: &Path| {
+        fs::metadata(path)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    };
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+    let sftp_fs = sftp.fs();
+
+    for upload in [true, false] {
+        let transfer = |check| {
+            let (src, dst) = (src.clone(), dst.clone());
+            let mut fs = sftp_fs.clone();
+            async move {
+                if upload {
+                    fs.reput(&src, &dst, check).await.unwrap()
+                } else {
+                    fs.reget(&src, &dst, check).await.unwrap()
+                }
+            }
+        };
+
+        // Resume from the existing prefix
+        fs::write(&dst, &content[..40_000]).unwrap();
+        fs::write(&src, &content).unwrap();
+        assert_eq!(transfer(ResumeCheck::Size).await, 60_000);
+        assert_eq!(fs::read(&dst).unwrap(), content);
+        assert_eq!(mtime(&dst), mtime(&src));
+
+        // Complete file is skipped
+        assert_eq!(transfer(ResumeCheck::Size).await, 0);
+
+        // Prefix not matching the checksum of the tail is overwritten
+        let mut corrupted = content[..40_000].to_vec();
+        corrupted[39_999] ^= 1;
+        fs::write(&dst, &corrupted).unwrap();
+        assert_eq!(transfer(ResumeCheck::TailChecksum(1024)).await, 100_000);
+        assert_eq!(fs::read(&dst).unwrap(), content);
+
+        // Matching prefix is resumed
+        fs::write(&dst, &content[..40_000]).unwrap();
+        assert_eq!(transfer(ResumeCheck::TailChecksum(1024)).await, 60_000);
+        assert_eq!(fs::read(&dst).unwrap(), content);
+
+        // Prefix older than the source is overwritten
+        fs::write(&dst, &content[..40_000]).unwrap();
+        sleep(Duration::from_secs(1)).await;
+        fs::write(&src, &content).unwrap();
+        assert_eq!(transfer(ResumeCheck::SizeAndMtime).await, 100_000);
+        assert_eq!(fs::read(&dst).unwrap(), content);
+
+        // Destination larger than the source is overwritten
+        fs::write(&dst, [&content[..], &content[..10]].concat()).unwrap();
+        assert_eq!(transfer(ResumeCheck::Size).await, 100_000);
+        assert_eq!(fs::read(&dst).unwrap(), content);
+
+        fs::remove_file(&dst).unwrap();
+        assert_eq!(transfer(ResumeCheck::Size).await, 100_000);
+        assert_eq!(fs::read(&dst).unwrap(), content);
+        fs::remove_file(&dst).unwrap();
+    }
+
+    drop(sftp_fs);
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Fs::write_atomic and AtomicFile
+async fn sftp_fs_write_atomic() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = gen_path("sftp_fs_write_atomic");
+    fs::create_dir_all(&path).unwrap();
+
+    let target = path.join("file");
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    let (mut child, sftp) = connect(Default::default()).await;
+    let mut sftp_fs = sftp.fs();
+
+    // Create a new file
+    sftp_fs.write_atomic(&target, b"hello").await.unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"hello");
+
+    // Permissions of the replaced file are kept
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).unwrap();
+    sftp_fs.write_atomic(&target, b"world").await.unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"world");
+    assert_eq!(mode(&target), 0o640);
+
+    // Target is unchanged until committed
+    let mut file = sftp_fs.create_atomic(&target).await.unwrap();
+    file.permissions(metadata::Permissions::from(0o600));
+    file.write_all(b"atomic").await.unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"world");
+    assert_eq!(fs::read(file.temp_path()).unwrap(), b"atomic");
+    file.commit().await.unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"atomic");
+    assert_eq!(mode(&target), 0o600);
+
+    // Discarded file is removed
+    let mut file = sftp_fs.create_atomic(&target).await.unwrap();
+    file.write_all(b"discarded").await.unwrap();
+    let temp_path = file.temp_path().to_owned();
+    file.discard().await.unwrap();
+    assert!(!temp_path.exists());
+
+    // Dropped file is removed
+    let mut file = sftp_fs.create_atomic(&target).await.unwrap();
+    file.write_all(b"dropped").await.unwrap();
+    let temp_path = file.temp_path().to_owned();
+    drop(file);
+
+    drop(sftp_fs);
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+
+    assert!(!temp_path.exists());
+    assert_eq!(fs::read(&target).unwrap(), b"atomic");
+    assert_eq!(fs::read_dir(&path).unwrap().count(), 1);
+}
+
+#[tokio::test]
+/// Test File::read_all_into and Fs::download_file
+async fn sftp_file_read_all_into() {
+    let path = gen_path("sftp_file_read_all_into");
+    fs::create_dir_all(&path).unwrap();
+
+    let content: Vec<u8> = (0..30_011).map(|i| (i % 251) as u8).collect();
+    fs::write(path.join("src"), &content).unwrap();
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+
+    for max_requests in [1, 3, 64] {
+        let max_requests = NonZeroUsize::new(max_requests).unwrap();
+        let mut file = sftp.open(path.join("src")).await.unwrap();
+
+        let mut buffer = Vec::new();
+        let n = file.read_all_into(&mut buffer, max_requests).await.unwrap();
+        assert_eq!(n, content.len() as u64);
+        assert_eq!(buffer, content);
+        assert_eq!(file.offset(), content.len() as u64);
+
+        // Read from the middle of the file
+        file.seek(std::io::SeekFrom::Start(10_001)).await.unwrap();
+        let mut buffer = Vec::new();
+        file.read_all_into(&mut buffer, max_requests).await.unwrap();
+        assert_eq!(buffer, &content[10_001..]);
+
+        // Read at EOF
+        let mut buffer = Vec::new();
+        assert_eq!(
+            file.read_all_into(&mut buffer, max_requests).await.unwrap(),
+            0
+        );
+        assert!(buffer.is_empty());
+
+        file.close().await.unwrap();
+    }
+
+    {
+        let mut sftp_fs = sftp.fs();
+        sftp_fs.set_cwd(&path);
+
+        fs::write(path.join("dst"), b"to be overwritten").unwrap();
+        let n = sftp_fs
+            .download_file("src", path.join("dst"))
+            .await
+            .unwrap();
+        assert_eq!(n, content.len() as u64);
+        assert_eq!(fs::read(path.join("dst")).unwrap(), content);
+    }
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test File::write_all_from and Fs::upload_file
+async fn sftp_file_write_all_from() {
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    /// Reader failing on the first read.
+    struct FailingReader;
+
+    impl AsyncRead for FailingReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "failing reader",
+            )))
+        }
+    }
+
+    let path = gen_path("sftp_file_write_all_from");
+    fs::create_dir_all(&path).unwrap();
+
+    let content: Vec<u8> = (0..30_011).map(|i| (i % 251) as u8).collect();
+    let dst = path.join("dst");
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+
+    for max_requests in [1, 3, 64] {
+        let max_requests = NonZeroUsize::new(max_requests).unwrap();
+        let mut file = sftp.create(&dst).await.unwrap();
+
+        let n = file
+            .write_all_from(&mut &content[..], max_requests)
+            .await
+            .unwrap();
+        assert_eq!(n, content.len() as u64);
+        assert_eq!(file.offset(), content.len() as u64);
+        assert_eq!(fs::read(&dst).unwrap(), content);
+
+        // Offset on failure is where the upload can be resumed from
+        file.seek(std::io::SeekFrom::Start(0)).await.unwrap();
+        fs::write(&dst, b"").unwrap();
+
+        let mut reader = (&content[..]).chain(FailingReader);
+        file.write_all_from(&mut reader, max_requests)
+            .await
+            .unwrap_err();
+
+        let offset = file.offset() as usize;
+        assert!(offset <= content.len());
+        assert_eq!(&fs::read(&dst).unwrap()[..offset], &content[..offset]);
+
+        file.write_all_from(&mut &content[offset..], max_requests)
+            .await
+            .unwrap();
+        assert_eq!(fs::read(&dst).unwrap(), content);
+
+        file.close().await.unwrap();
+    }
+
+    {
+        let mut sftp_fs = sftp.fs();
+        sftp_fs.set_cwd(&path);
+
+        fs::write(path.join("src"), &content).unwrap();
+        fs::write(&dst, b"to be overwritten, which is longer than content").unwrap();
+        let n = sftp_fs.upload_file(path.join("src"), "dst").await.unwrap();
+        assert_eq!(n, content.len() as u64);
+        assert_eq!(fs::read(&dst).unwrap(), content);
+    }
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test Progress with TokioCompatFile, Uploader and Downloader
+async fn sftp_progress() {
+    use std::sync::{Arc, Mutex};
+
+    let path = gen_path("sftp_progress");
+    fs::create_dir_all(&path).unwrap();
+
+    let content: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+
+    let new_progress = || {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress = {
+            let reports = Arc::clone(&reports);
+            Progress::new(move |progress: TransferProgress| reports.lock().unwrap().push(progress))
+        };
+        (progress, reports)
+    };
+    let check_reports = |reports: &Mutex<Vec<TransferProgress>>, total: Option<u64>| {
+        let reports = reports.lock().unwrap();
+        assert!(!reports.is_empty());
+        assert!(reports.windows(2).all(|w| w[0].bytes() < w[1].bytes()));
+        let last = reports.last().unwrap();
+        assert_eq!(last.bytes(), content.len() as u64);
+        assert_eq!(last.total(), total);
+    };
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+
+    // Writes are reported once acknowledged
+    {
+        let (progress, reports) = new_progress();
+        progress.set_total(Some(content.len() as u64));
+
+        let mut file = sftp.create(path.join("file")).await.unwrap();
+        file.set_progress(Some(progress));
+        let file = file::TokioCompatFile::new(file);
+        tokio::pin!(file);
+
+        file.write_all(&content).await.unwrap();
+        file.flush().await.unwrap();
+
+        check_reports(&reports, Some(content.len() as u64));
+    }
+
+    // Reads are reported once received
+    {
+        let (progress, reports) = new_progress();
+
+        let mut file = sftp.open(path.join("file")).await.unwrap();
+        file.set_progress(Some(progress));
+        let file = file::TokioCompatFile::new(file);
+        tokio::pin!(file);
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).await.unwrap();
+        assert_eq!(buffer, content);
+
+        check_reports(&reports, None);
+    }
+
+    // Total is set when transferring a single file
+    {
+        let mut sftp_fs = sftp.fs();
+
+        let (progress, reports) = new_progress();
+        sftp_fs
+            .uploader()
+            .progress(progress)
+            .upload(path.join("file"), path.join("uploaded"))
+            .await
+            .unwrap();
+        check_reports(&reports, Some(content.len() as u64));
+
+        let (progress, reports) = new_progress();
+        sftp_fs
+            .downloader()
+            .progress(progress)
+            .download(path.join("uploaded"), path.join("downloaded"))
+            .await
+            .unwrap();
+        check_reports(&reports, Some(content.len() as u64));
+        assert_eq!(fs::read(path.join("downloaded")).unwrap(), content);
+    }
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test SftpOptions::upload_rate_limit and SftpOptions::download_rate_limit
+async fn sftp_rate_limit() {
+    let path = gen_path("sftp_rate_limit");
+    let rate = NonZeroU64::new(16 * 1024).unwrap();
+    let content = vec![b'r'; 48 * 1024];
+
+    let options = sftp_options_with_max_rw_len()
+        .upload_rate_limit(rate)
+        .download_rate_limit(rate);
+    let (mut child, sftp) = connect(options).await;
+
+    assert_eq!(sftp.upload_rate_limit(), Some(rate));
+    assert_eq!(sftp.download_rate_limit(), Some(rate));
+
+    // The first second worth of bytes is sent in a burst,
+    // the rest takes at least another two seconds.
+    let start = Instant::now();
+    let mut file = sftp.create(&path).await.unwrap();
+    file.write_all(&content).await.unwrap();
+    file.close().await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(1500));
+
+    let start = Instant::now();
+    let mut file = sftp.open(&path).await.unwrap();
+    let buffer = file.read_all(content.len(), BytesMut::new()).await.unwrap();
+    assert_eq!(&*buffer, &*content);
+    drop(file);
+    assert!(start.elapsed() >= Duration::from_millis(1500));
+
+    // Remove the limits at runtime
+    sftp.set_upload_rate_limit(None);
+    sftp.set_download_rate_limit(None);
+    assert_eq!(sftp.upload_rate_limit(), None);
+    assert_eq!(sftp.download_rate_limit(), None);
+
+    let start = Instant::now();
+    let mut file = sftp.create(&path).await.unwrap();
+    file.write_all(&content).await.unwrap();
+    file.close().await.unwrap();
+    assert!(start.elapsed() < Duration::from_millis(1500));
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test File::read_at, File::write_at, File::write_vectorized_at
+/// and File::write_zero_copy_at
+async fn sftp_file_read_write_at() {
+    let path = gen_path("sftp_file_read_write_at");
+    let content: Vec<u8> = (0..600).map(|i| i as u8).collect();
+    let (first, rest) = content.split_at(150);
+    let (second, rest) = rest.split_at(150);
+    let (third, fourth) = rest.split_at(150);
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+
+    let file = sftp
+        .options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(&path)
+        .await
+        .unwrap();
+
+    let io_slices = [IoSlice::new(&second[..50]), IoSlice::new(&second[50..])];
+    let bytes = [Bytes::copy_from_slice(third)];
+
+    let (n0, n1, n2, n3) = tokio::try_join!(
+        file.write_at(450, fourth),
+        file.write_at(0, first),
+        file.write_vectorized_at(150, &io_slices),
+        file.write_zero_copy_at(300, &bytes),
+    )
+    .unwrap();
+    assert_eq!((n0, n1, n2, n3), (150, 150, 150, 150));
+    assert_eq!(file.offset(), 0);
+
+    let (b0, b1, b2) = tokio::try_join!(
+        file.read_at(300, 150, BytesMut::new()),
+        file.read_at(0, 150, BytesMut::new()),
+        file.read_at(600, 150, BytesMut::new()),
+    )
+    .unwrap();
+    assert_eq!(&*b0.unwrap(), third);
+    assert_eq!(&*b1.unwrap(), first);
+    assert_eq!(b2, None);
+    assert_eq!(file.offset(), 0);
+
+    drop(file);
+
+    assert_eq!(fs::read(&path).unwrap(), content);
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test File::into_stream
+async fn sftp_file_into_stream() {
+    let path = gen_path("sftp_file_into_stream");
+    let content: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+    fs::write(&path, &content).unwrap();
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+    let max_requests = NonZeroUsize::new(4).unwrap();
+
+    let mut file = sftp.open(&path).await.unwrap();
+    file.seek(std::io::SeekFrom::Start(100)).await.unwrap();
+
+    let stream = file.into_stream(max_requests);
+    tokio::pin!(stream);
+    let mut buffer = Vec::new();
+    while let Some(bytes) = stream.next().await {
+        let bytes = bytes.unwrap();
+        assert!(!bytes.is_empty() && bytes.len() <= 200);
+        buffer.extend_from_slice(&bytes);
+    }
+    assert_eq!(buffer, &content[100..]);
+    assert!(stream.next().await.is_none());
+
+    // Dropping the stream with read requests in flight
+    let mut stream = Box::pin(sftp.open(&path).await.unwrap().into_stream(max_requests));
+    assert_eq!(&*stream.next().await.unwrap().unwrap(), &content[..200]);
+    drop(stream);
+
+    // The file is not opened for reading
+    let stream = sftp
+        .options()
+        .write(true)
+        .open(&path)
+        .await
+        .unwrap()
+        .into_stream(max_requests);
+    tokio::pin!(stream);
+    assert!(stream.next().await.unwrap().is_err());
+    assert!(stream.next().await.is_none());
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[tokio::test]
+/// Test File::into_sink
+async fn sftp_file_into_sink() {
+    let path = gen_path("sftp_file_into_sink");
+    let content: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+    let max_requests = NonZeroUsize::new(2).unwrap();
+
+    let sink = sftp.create(&path).await.unwrap().into_sink(max_requests);
+    tokio::pin!(sink);
+
+    // Items smaller and larger than max_write_len
+    let items = [&content[..150], &content[150..1500], &content[1500..]];
+    for item in items {
+        poll_fn(|cx| Sink::<Bytes>::poll_ready(sink.as_mut(), cx))
+            .await
+            .unwrap();
+        sink.as_mut()
+            .start_send(Bytes::copy_from_slice(item))
+            .unwrap();
+    }
+    poll_fn(|cx| Sink::<Bytes>::poll_close(sink.as_mut(), cx))
+        .await
+        .unwrap();
+    assert_eq!(sink.offset(), content.len() as u64);
+
+    assert_eq!(fs::read(&path).unwrap(), content);
+
+    // The file is not opened for writing
+    let sink = sftp.open(&path).await.unwrap().into_sink(max_requests);
+    tokio::pin!(sink);
+    assert!(poll_fn(|cx| Sink::<Bytes>::poll_ready(sink.as_mut(), cx))
+        .await
+        .is_err());
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[cfg(feature = "buf-list")]
+#[tokio::test]
+/// Test Sink<BufList> for FileSink
+async fn sftp_file_into_sink_buf_list() {
+    let path = gen_path("sftp_file_into_sink_buf_list");
+    let content: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+
+    let (mut child, sftp) = connect(sftp_options_with_max_rw_len()).await;
+
+    let sink = sftp
+        .create(&path)
+        .await
+        .unwrap()
+        .into_sink(NonZeroUsize::new(2).unwrap());
+    tokio::pin!(sink);
+
+    // Items larger than max_write_len, made of chunks not aligned to it
+    for range in [0..1000, 1000..2000] {
+        let mut item = buf_list::BufList::new();
+        for chunk in content[range].chunks(30) {
+            item.push_chunk(Bytes::copy_from_slice(chunk));
+        }
+
+        poll_fn(|cx| Sink::<buf_list::BufList>::poll_ready(sink.as_mut(), cx))
+            .await
+            .unwrap();
+        sink.as_mut().start_send(item).unwrap();
+    }
+    poll_fn(|cx| Sink::<buf_list::BufList>::poll_close(sink.as_mut(), cx))
+        .await
+        .unwrap();
+    assert_eq!(sink.offset(), content.len() as u64);
+
+    assert_eq!(fs::read(&path).unwrap(), content);
+
+    sftp.close().await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
+
 #[tokio::test]
 /// Test File::copy_to
 async fn sftp_file_copy_to() {